@@ -0,0 +1,9 @@
+#![no_main]
+
+extern crate json_ns;
+#[macro_use]
+extern crate libfuzzer_sys;
+
+fuzz_target!(|data: &[u8]| {
+    json_ns::fuzz::process(data);
+});
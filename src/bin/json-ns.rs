@@ -0,0 +1,89 @@
+//! Command-line interface to the JSON-NS processor.
+//!
+//! Reads a document from stdin, processes it, and writes the result to stdout.
+
+extern crate json_ns;
+extern crate serde_json as json;
+
+use json::Value;
+use json_ns::{Context, Processor};
+use std::env;
+use std::fs::File;
+use std::io::{self, Write};
+use std::process;
+
+const USAGE: &str = "\
+Usage: json-ns [options] < input.json
+
+Options:
+    --rule PREFIX=BASE    Add a rule to the target context (repeatable)
+    --context FILE        Use the JSON in FILE as the external context
+    --pretty              Pretty-print the output
+    --help                Show this help";
+
+/// Print an error message and exit.
+fn fail(message: &str) -> ! {
+    eprintln!("json-ns: {}", message);
+    process::exit(1);
+}
+
+/// Print an error message with the usage text and exit.
+fn usage_error(message: &str) -> ! {
+    eprintln!("json-ns: {}\n\n{}", message, USAGE);
+    process::exit(2);
+}
+
+fn main() {
+    let mut processor = Processor::new();
+    let mut pretty = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--rule" => {
+                let rule = args.next()
+                    .unwrap_or_else(|| usage_error("--rule requires an argument"));
+                let mut parts = rule.splitn(2, '=');
+                let prefix = parts.next().unwrap();
+                let base = parts.next()
+                    .unwrap_or_else(|| usage_error("--rule must be of the form PREFIX=BASE"));
+                processor.add_rule(prefix, base);
+            },
+            "--context" => {
+                let path = args.next()
+                    .unwrap_or_else(|| usage_error("--context requires an argument"));
+                let file = File::open(&path)
+                    .unwrap_or_else(|err| fail(&format!("could not open {}: {}", path, err)));
+                let value: Value = json::from_reader(file)
+                    .unwrap_or_else(|err| fail(&format!("could not parse {}: {}", path, err)));
+                processor.context = Context::from(&value);
+            },
+            "--pretty" => {
+                pretty = true;
+            },
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                return;
+            },
+            _ => {
+                usage_error(&format!("unrecognized argument: {}", arg));
+            },
+        }
+    }
+
+    let stdin = io::stdin();
+    let input: Value = json::from_reader(stdin.lock())
+        .unwrap_or_else(|err| fail(&format!("could not parse input: {}", err)));
+
+    let output = processor.process_value(&input);
+    let output = if pretty {
+        json::to_string_pretty(&output)
+    } else {
+        json::to_string(&output)
+    }.expect("cannot serialize output value");
+
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    writeln!(stdout, "{}", output)
+        .unwrap_or_else(|err| fail(&format!("could not write output: {}", err)));
+}
@@ -0,0 +1,155 @@
+//! Error types.
+
+use json;
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// A limit configured on the `Processor`.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub enum Limit {
+    /// The `max_properties` limit.
+    Properties,
+    /// The `max_output_nodes` limit.
+    OutputNodes,
+    /// The `max_contexts` limit.
+    Contexts,
+    /// The `max_depth` limit.
+    Depth,
+}
+
+/// A property that was dropped from the output during strict processing.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Dropped {
+    /// The property name as it appeared in the input.
+    pub property: String,
+    /// A JSON pointer to the property in the input, such as `/@graph/3/foo:bar`.
+    pub path: String,
+}
+
+/// An issue found when validating a context.
+///
+/// Each variant holds a JSON pointer to the offending value, relative to the validated value. For
+/// a `TargetContext`, the pointer is relative to its rules, as if each rule was an array of the
+/// prefix and base IRI.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Warning {
+    /// A keyword that is not recognized, and will be ignored.
+    UnknownKeyword(String),
+    /// A value that should be an absolute IRI, but isn't.
+    InvalidIri(String),
+    /// A term aliased to `@context`, which will be ignored.
+    KeywordAlias(String),
+    /// A container mapping that is not recognized.
+    UnknownContainer(String),
+    /// A definition that has an unexpected type, or defines a name that can't be a term.
+    InvalidDefinition(String),
+    /// A prefix that was already defined earlier.
+    DuplicatePrefix(String),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Warning::UnknownKeyword(ref path) => write!(f, "{}: unknown keyword", path),
+            Warning::InvalidIri(ref path) => write!(f, "{}: not an absolute IRI", path),
+            Warning::KeywordAlias(ref path) => write!(f, "{}: alias of a keyword", path),
+            Warning::UnknownContainer(ref path) => write!(f, "{}: unknown container", path),
+            Warning::InvalidDefinition(ref path) => write!(f, "{}: invalid definition", path),
+            Warning::DuplicatePrefix(ref path) => write!(f, "{}: duplicate prefix", path),
+        }
+    }
+}
+
+/// Errors returned by fallible operations in this crate.
+#[derive(Debug)]
+pub enum JsonNsError {
+    /// Input could not be parsed as JSON.
+    Json(json::Error),
+    /// A remote context could not be loaded.
+    Loader {
+        /// The IRI of the remote context.
+        iri: String,
+        /// The error returned by the `ContextLoader`.
+        error: io::Error,
+    },
+    /// A remote context (indirectly) references itself.
+    CyclicContext(String),
+    /// Processing exceeded one of the configured limits.
+    LimitExceeded(Limit),
+    /// A context attempted to redefine a protected term.
+    ProtectedTerm(String),
+    /// A context attempted to redefine a prefix with a different base IRI, while
+    /// `Context::prefix_redefinition` is `PrefixRedefinition::Keep`.
+    PrefixRedefined(String),
+    /// A feature of JSON-LD 1.1 was used without declaring `"@version": 1.1`, while
+    /// `Processor::require_version` is set. Holds the keyword of the feature.
+    VersionRequired(String),
+    /// Properties were dropped from the output, because they could not be resolved.
+    Dropped(Vec<Dropped>),
+    /// The root of a document is not an object or array.
+    NotADocument,
+}
+
+impl fmt::Display for JsonNsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            JsonNsError::Json(ref error) => {
+                write!(f, "invalid JSON: {}", error)
+            },
+            JsonNsError::Loader { ref iri, ref error } => {
+                write!(f, "could not load context {}: {}", iri, error)
+            },
+            JsonNsError::CyclicContext(ref iri) => {
+                write!(f, "cyclic reference to context {}", iri)
+            },
+            JsonNsError::LimitExceeded(Limit::Properties) => {
+                write!(f, "document exceeds the maximum number of properties")
+            },
+            JsonNsError::LimitExceeded(Limit::OutputNodes) => {
+                write!(f, "output exceeds the maximum number of nodes")
+            },
+            JsonNsError::LimitExceeded(Limit::Contexts) => {
+                write!(f, "document exceeds the maximum number of remote contexts")
+            },
+            JsonNsError::LimitExceeded(Limit::Depth) => {
+                write!(f, "document exceeds the maximum nesting depth")
+            },
+            JsonNsError::ProtectedTerm(ref term) => {
+                write!(f, "attempt to redefine protected term: {}", term)
+            },
+            JsonNsError::PrefixRedefined(ref prefix) => {
+                write!(f, "attempt to redefine prefix: {}", prefix)
+            },
+            JsonNsError::VersionRequired(ref feature) => {
+                write!(f, "{} requires @version 1.1", feature)
+            },
+            JsonNsError::Dropped(ref dropped) => {
+                write!(f, "dropped {} unresolved properties:", dropped.len())?;
+                for dropped in dropped {
+                    write!(f, " {}", dropped.path)?;
+                }
+                Ok(())
+            },
+            JsonNsError::NotADocument => {
+                write!(f, "document root must be an object or array")
+            },
+        }
+    }
+}
+
+impl Error for JsonNsError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            JsonNsError::Json(ref error) => Some(error),
+            JsonNsError::Loader { ref error, .. } => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<json::Error> for JsonNsError {
+    fn from(error: json::Error) -> JsonNsError {
+        JsonNsError::Json(error)
+    }
+}
@@ -0,0 +1,46 @@
+//! Entry point for fuzzing, only available when built with `cfg(fuzzing)`.
+
+use json::{self, Value};
+use {compact, expand, AutoPrefix, Context, Processor, TargetContext};
+
+/// Parse arbitrary input as JSON, and run it through the public processing functions.
+///
+/// Input that is not valid JSON is ignored. Everything else must be handled without panicking,
+/// which is what the fuzz target checks.
+pub fn process(data: &[u8]) {
+    let value: Value = match json::from_slice(data) {
+        Ok(value) => value,
+        Err(_) => return,
+    };
+
+    let mut target = TargetContext::new();
+    target.add_rule("", "http://example.com/vocab#");
+    target.add_rule("ex", "http://example.com/");
+
+    // Use the document itself as the external context as well, to exercise unusual contexts
+    // without relying on the fuzzer to nest them.
+    let context = Context::from(&value);
+    let _ = Context::validate(&value);
+    let _ = context.difference(&Context::new());
+
+    let mut processor = Processor::with_context(context).with_target(target.clone());
+    processor.max_depth = Some(64);
+    let _ = processor.process_value(&value);
+    let _ = processor.process_value_strict(&value);
+    let _ = processor.flatten(&value);
+    let _ = processor.triples(&value).count();
+    let _ = processor.roundtrip_check(&value);
+
+    processor.auto_prefix = Some(AutoPrefix::default());
+    processor.wrap_strings = true;
+    processor.sort_keys = true;
+    processor.strip_blank_nodes = true;
+    processor.index_key = Some("@index".to_owned());
+    processor.trim_strings = true;
+    processor.order_key = Some("ex:order".to_owned());
+    let _ = processor.process_value(&value);
+    let _ = processor.process_value_strict(&value);
+
+    let _ = expand(&value, &Context::new());
+    let _ = compact(&value, &target);
+}
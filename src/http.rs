@@ -0,0 +1,67 @@
+//! A `ContextLoader` that fetches remote contexts over HTTP, enabled with the `http` feature.
+
+use json::{self, Value};
+use reqwest::blocking::Client;
+use reqwest::header::ACCEPT;
+use std::io::{self, Read};
+use std::time::Duration;
+use ContextLoader;
+
+/// Loads remote contexts over HTTP using a blocking `reqwest` client.
+///
+/// Only `http` and `https` IRIs are loaded. Because context IRIs come from the documents being
+/// processed, requests are bounded by a timeout and a maximum response size.
+#[derive(Clone,Debug)]
+pub struct HttpLoader {
+    /// Timeout for each request, including reading the body. Defaults to 10 seconds.
+    pub timeout: Duration,
+    /// Maximum size of a response body in bytes. Larger responses are rejected. Defaults to 1 MiB.
+    pub max_size: u64,
+    client: Client,
+}
+
+impl HttpLoader {
+    /// Create a loader with the default limits.
+    pub fn new() -> HttpLoader {
+        HttpLoader::with_client(Client::new())
+    }
+
+    /// Create a loader using an existing client, with the default limits.
+    pub fn with_client(client: Client) -> HttpLoader {
+        HttpLoader {
+            timeout: Duration::from_secs(10),
+            max_size: 1024 * 1024,
+            client,
+        }
+    }
+}
+
+impl Default for HttpLoader {
+    fn default() -> HttpLoader {
+        HttpLoader::new()
+    }
+}
+
+impl ContextLoader for HttpLoader {
+    fn load(&self, iri: &str) -> io::Result<Value> {
+        if !iri.starts_with("http://") && !iri.starts_with("https://") {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "not an HTTP IRI"));
+        }
+
+        let response = self.client.get(iri)
+            .header(ACCEPT, "application/ld+json, application/json")
+            .timeout(self.timeout)
+            .send()
+            .and_then(|response| response.error_for_status())
+            .map_err(io::Error::other)?;
+
+        // Read at most one byte past the limit, so we can tell if the body is too large.
+        let mut body = Vec::new();
+        response.take(self.max_size + 1).read_to_end(&mut body)?;
+        if body.len() as u64 > self.max_size {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "response too large"));
+        }
+
+        Ok(json::from_slice(&body)?)
+    }
+}
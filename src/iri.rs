@@ -0,0 +1,146 @@
+//! Resolution of relative IRI references, following RFC 3986.
+
+/// The components of an IRI reference.
+struct Parts<'a> {
+    scheme: Option<&'a str>,
+    authority: Option<&'a str>,
+    path: &'a str,
+    query: Option<&'a str>,
+    fragment: Option<&'a str>,
+}
+
+impl<'a> Parts<'a> {
+    /// Split an IRI reference into its components.
+    fn parse(input: &'a str) -> Parts<'a> {
+        let (input, fragment) = match input.find('#') {
+            Some(index) => (&input[..index], Some(&input[index + 1..])),
+            None => (input, None),
+        };
+        let (input, query) = match input.find('?') {
+            Some(index) => (&input[..index], Some(&input[index + 1..])),
+            None => (input, None),
+        };
+
+        // Anything before the first colon that is not a valid scheme, for example because it
+        // contains a slash, is part of the path.
+        let scheme_end = input.find(':').filter(|&index| is_scheme(&input[..index]));
+        let (scheme, input) = match scheme_end {
+            Some(index) => (Some(&input[..index]), &input[index + 1..]),
+            None => (None, input),
+        };
+
+        let (authority, path) = if let Some(input) = input.strip_prefix("//") {
+            match input.find('/') {
+                Some(index) => (Some(&input[..index]), &input[index..]),
+                None => (Some(input), ""),
+            }
+        } else {
+            (None, input)
+        };
+
+        Parts { scheme, authority, path, query, fragment }
+    }
+}
+
+/// Whether the input is a valid scheme. Schemes are case-insensitive, so letters may be in either
+/// case.
+pub fn is_scheme(input: &str) -> bool {
+    let mut chars = input.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+}
+
+/// Resolve an IRI reference against an absolute base IRI.
+///
+/// If the reference is already absolute, only its dot segments are removed.
+pub fn resolve(base: &str, reference: &str) -> String {
+    let base = Parts::parse(base);
+    let reference = Parts::parse(reference);
+
+    let (scheme, authority, path, query);
+    if reference.scheme.is_some() {
+        scheme = reference.scheme;
+        authority = reference.authority;
+        path = remove_dot_segments(reference.path);
+        query = reference.query;
+    } else {
+        scheme = base.scheme;
+        if reference.authority.is_some() {
+            authority = reference.authority;
+            path = remove_dot_segments(reference.path);
+            query = reference.query;
+        } else {
+            authority = base.authority;
+            if reference.path.is_empty() {
+                path = base.path.to_owned();
+                query = reference.query.or(base.query);
+            } else {
+                path = if reference.path.starts_with('/') {
+                    remove_dot_segments(reference.path)
+                } else {
+                    remove_dot_segments(&merge_paths(&base, reference.path))
+                };
+                query = reference.query;
+            }
+        }
+    }
+
+    let mut result = String::new();
+    if let Some(scheme) = scheme {
+        result.push_str(scheme);
+        result.push(':');
+    }
+    if let Some(authority) = authority {
+        result.push_str("//");
+        result.push_str(authority);
+    }
+    result.push_str(&path);
+    if let Some(query) = query {
+        result.push('?');
+        result.push_str(query);
+    }
+    if let Some(fragment) = reference.fragment {
+        result.push('#');
+        result.push_str(fragment);
+    }
+    result
+}
+
+/// Merge a relative path with the path of the base.
+fn merge_paths(base: &Parts, path: &str) -> String {
+    if base.authority.is_some() && base.path.is_empty() {
+        format!("/{}", path)
+    } else {
+        match base.path.rfind('/') {
+            Some(index) => format!("{}{}", &base.path[..=index], path),
+            None => path.to_owned(),
+        }
+    }
+}
+
+/// Remove `.` and `..` segments from a path.
+fn remove_dot_segments(path: &str) -> String {
+    let mut output: Vec<&str> = Vec::new();
+    let mut input = path;
+    while !input.is_empty() {
+        if let Some(rest) = input.strip_prefix("../").or_else(|| input.strip_prefix("./")) {
+            input = rest;
+        } else if input.starts_with("/./") {
+            input = &input[2..];
+        } else if input == "/." {
+            input = "/";
+        } else if input.starts_with("/../") || input == "/.." {
+            input = if input == "/.." { "/" } else { &input[3..] };
+            output.pop();
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            // Move the first segment, including a leading slash, to the output.
+            let start = usize::from(input.starts_with('/'));
+            let end = input[start..].find('/').map_or(input.len(), |index| index + start);
+            output.push(&input[..end]);
+            input = &input[end..];
+        }
+    }
+    output.concat()
+}
@@ -56,11 +56,20 @@
 //! Note that the output should *not* itself be considered a JSON-NS document. Running input
 //! through a processor twice may produce unexpected results.
 //!
+//! Values are copied to the output as-is. With the `arbitrary_precision` feature enabled, which
+//! enables the same feature in `serde_json`, numbers are kept exactly as they appear in the input.
+//!
+//! Without the `preserve_order` feature, `serde_json` objects are sorted maps, so keys in the
+//! output are always sorted. With it, keys follow the order of the input, and the output can be
+//! ordered using `Processor::sort_keys`. That setting has no effect without the feature, because
+//! a sorted map can't hold its keys in any other order.
+//!
 //! That should cover the basics. More details can be found in the documentation of the structs,
 //! fields and functions.
 
 #[macro_use]
 extern crate cfg_if;
+#[cfg_attr(test, macro_use)]
 extern crate serde_json as json;
 
 cfg_if! {
@@ -70,63 +79,196 @@ cfg_if! {
     }
 }
 
-use json::Value;
-use std::borrow::Cow;
-use std::collections::BTreeMap;
-use std::slice::Iter;
+mod error;
+mod iri;
+mod rdf;
+mod stream;
+pub mod util;
 
-type Map = json::Map<String, Value>;
+pub use error::{Dropped, JsonNsError, Limit, Warning};
+pub use rdf::{Literal, Object, Subject, Triple};
+pub use stream::ProcessReader;
 
-/// Iterator used to walk a value that may or may not be an array.
-enum OneOrMany<'a> {
-  None,
-  One(&'a Value),
-  Many(Iter<'a, Value>),
+cfg_if! {
+    if #[cfg(feature = "http")] {
+        extern crate reqwest;
+        mod http;
+        pub use http::HttpLoader;
+    }
 }
 
-impl<'a> From<&'a Value> for OneOrMany<'a> {
-    fn from(value: &'a Value) -> Self {
-        match *value {
-            Value::Array(ref arr) => OneOrMany::Many(arr.iter()),
-            ref value => OneOrMany::One(value),
-        }
+cfg_if! {
+    if #[cfg(fuzzing)] {
+        pub mod fuzz;
     }
 }
 
-impl<'a> Iterator for OneOrMany<'a> {
-    type Item = &'a Value;
-    fn next(&mut self) -> Option<&'a Value> {
-        match *self {
-            OneOrMany::None => None,
-            OneOrMany::One(value) => {
-                *self = OneOrMany::None;
-                Some(value)
-            },
-            OneOrMany::Many(ref mut iter) => {
-                iter.next()
-            },
-        }
+cfg_if! {
+    if #[cfg(feature = "presets")] {
+        mod presets;
+    }
+}
+
+cfg_if! {
+    if #[cfg(feature = "wasm")] {
+        extern crate wasm_bindgen;
+        pub mod wasm;
     }
 }
 
+use json::Value;
+use std::borrow::Cow;
+use std::collections::{btree_map, BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fmt::{self, Debug, Display};
+use std::io;
+use std::iter::FromIterator;
+use std::mem;
+use std::sync::Arc;
+use util::OneOrMany;
+
+type Map = json::Map<String, Value>;
+
 /// Structure holding the current context to interpret a document with.
 ///
 /// An instance of this struct is part of the `Processor`, which can be modified to provide an
 /// external context to interpret documents with. Such a custom context can also be created from
 /// JSON using one of the `From` implementations.
-#[derive(Clone,Debug,Default)]
+///
+/// Contexts compare equal if everything defined in them is equal, regardless of the order in which
+/// definitions were merged. They can also be hashed, for example to use them as cache keys.
+#[derive(Clone,Debug,Default,PartialEq,Eq,Hash)]
 pub struct Context {
     /// The default namespace, for properties that are not a keyword, CURIE, or IRI.
     pub ns: Option<String>,
     /// The Default language for internationalised properties that don't specify one. The empty
     /// string when not defined.
     pub lang: String,
+    /// The default base direction for internationalised properties, either `ltr` or `rtl`.
+    ///
+    /// A language map has no place for a direction, so this only appears in expanded form, on the
+    /// value objects created from language maps.
+    pub direction: Option<String>,
+    /// The base IRI to resolve relative `@id` values against.
+    pub base: Option<String>,
+    /// The processing mode declared with `@version`. Only `1.1` is recognized, stored as `"1.1"`.
+    pub version: Option<String>,
     /// Map of defined CURIE prefixes to their base IRIs.
+    ///
+    /// Terms defined as a string are prefixes. Terms defined as an object are only prefixes if
+    /// they have `"@prefix": true`.
     pub prefixes: BTreeMap<String, String>,
     /// Map of defined aliases by their literal property names.
     pub aliases: BTreeMap<String, String>,
     /// Map of defined container mappings by their literal property names.
+    ///
+    /// When merging, the mapping of a term is also recorded under the absolute IRI it expands to,
+    /// so that it applies to properties using the IRI, or another term for it.
     pub container: BTreeMap<String, String>,
+    /// Set of terms with a `@set` container, possibly combined with a container mapping. The
+    /// values of these properties are always arrays, or language maps with arrays.
+    ///
+    /// Like `container`, this also holds the absolute IRIs of those terms after merging.
+    pub sets: BTreeSet<String>,
+    /// Map of type mappings by their literal property names.
+    ///
+    /// Currently, only `@json` and `@id` are recognized. The former copies the value of the property
+    /// as-is, while the latter makes strings in the value node references.
+    pub types: BTreeMap<String, String>,
+    /// Map of base directions by their literal property names, either `ltr` or `rtl`, or the empty
+    /// string for no direction, overriding the default `direction`.
+    ///
+    /// Like the default, these only appear in expanded form, on the value objects of strings.
+    pub directions: BTreeMap<String, String>,
+    /// Map of scoped contexts by their literal property names. These are merged into the active
+    /// context when processing the value of the property.
+    pub scoped: BTreeMap<String, Value>,
+    /// Set of protected terms, which may not be redefined by later contexts.
+    pub protected: BTreeSet<String>,
+    /// How to expand names with an undefined CURIE prefix. Defaults to `UnknownPrefix::Iri`.
+    ///
+    /// This is configuration rather than a definition, so it is not cleared by a null context.
+    pub unknown_prefix: UnknownPrefix,
+    /// The context to return to in nested nodes, set when merging `"@propagate": false`.
+    ///
+    /// Definitions from a non-propagating context apply only to the node it is used on, and not
+    /// to the nodes nested in it.
+    pub previous: Option<Box<Context>>,
+    /// Additional URI schemes to accept with `UnknownPrefix::Drop` or `UnknownPrefix::Vocab`, in
+    /// lowercase.
+    ///
+    /// This is configuration rather than a definition, so it is not cleared by a null context.
+    pub schemes: BTreeSet<String>,
+    /// How to handle a CURIE prefix that is defined again with a different base IRI. Defaults to
+    /// `PrefixRedefinition::Replace`.
+    ///
+    /// This is configuration rather than a definition, so it is not cleared by a null context.
+    pub prefix_redefinition: PrefixRedefinition,
+}
+
+/// How `Context::expand_name` handles a name containing a colon, when the part before the colon is
+/// not a defined CURIE prefix.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Hash)]
+pub enum UnknownPrefix {
+    /// Treat the name as an absolute IRI.
+    #[default]
+    Iri,
+    /// Treat the name as an absolute IRI only if the part before the colon is a well-known URI
+    /// scheme, or one listed in `Context::schemes`. Otherwise, the name is dropped. This catches
+    /// typos and missing prefix definitions, which would otherwise produce bogus IRIs.
+    Drop,
+    /// Like `Drop`, but instead of dropping the name, treat it as a term in the default namespace,
+    /// so `foo:bar` expands to the `@vocab` IRI followed by `foo:bar`. The name is still dropped if
+    /// there is no default namespace. Node identifiers in `@id` are never expanded this way, and
+    /// are dropped like with `Drop`.
+    Vocab,
+}
+
+/// How merging a context handles a CURIE prefix that is already defined with a different base IRI.
+///
+/// A null context or a null term definition clears a prefix, after which it may be defined again.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Hash)]
+pub enum PrefixRedefinition {
+    /// Replace the earlier definition.
+    #[default]
+    Replace,
+    /// Keep the earlier definition. This catches mistakes where a context accidentally reuses a
+    /// prefix name. In strict processing, `JsonNsError::PrefixRedefined` is returned instead.
+    Keep,
+}
+
+/// URI schemes accepted by `UnknownPrefix::Drop` and `UnknownPrefix::Vocab`.
+const KNOWN_SCHEMES: &[&str] = &[
+    "data", "did", "file", "ftp", "geo", "http", "https", "ipfs", "mailto", "tag", "tel", "urn",
+    "ws", "wss",
+];
+
+/// Everything defined for a single term in a `Context`.
+#[derive(Clone,Debug,Default,PartialEq)]
+struct Term {
+    prefix: Option<String>,
+    alias: Option<String>,
+    container: Option<String>,
+    set: bool,
+    ty: Option<String>,
+    direction: Option<String>,
+    scoped: Option<Value>,
+}
+
+/// Settings and state used while merging contexts.
+#[derive(Default)]
+struct Resolver<'a> {
+    /// Loader for remote contexts.
+    loader: Option<&'a dyn ContextLoader>,
+    /// Whether to abort on errors, instead of recovering from them.
+    strict: bool,
+    /// IRIs of remote contexts currently being merged, used to detect cycles.
+    loading: Vec<String>,
+    /// Number of remote contexts loaded so far.
+    loaded: usize,
+    /// Maximum number of remote contexts to load.
+    max_loaded: Option<usize>,
+    /// Whether strict merging requires `"@version": 1.1` for features of JSON-LD 1.1.
+    require_version: bool,
 }
 
 impl Context {
@@ -135,37 +277,429 @@ impl Context {
         Context::default()
     }
 
+    /// A short-hand for setting the default namespace.
+    pub fn with_vocab(&mut self, ns: &str) -> &mut Self {
+        self.ns = Some(ns.to_owned());
+        self
+    }
+
+    /// A short-hand for setting the default language.
+    pub fn with_language(&mut self, lang: &str) -> &mut Self {
+        self.lang = lang.to_owned();
+        self
+    }
+
+    /// A short-hand for defining a CURIE prefix.
+    pub fn add_prefix(&mut self, prefix: &str, base: &str) -> &mut Self {
+        self.prefixes.insert(prefix.to_owned(), base.to_owned());
+        self
+    }
+
+    /// A short-hand for defining an alias.
+    pub fn add_alias(&mut self, name: &str, alias: &str) -> &mut Self {
+        self.aliases.insert(name.to_owned(), alias.to_owned());
+        self
+    }
+
+    /// A short-hand for defining a container mapping.
+    pub fn add_container(&mut self, name: &str, container: &str) -> &mut Self {
+        self.container.insert(name.to_owned(), container.to_owned());
+        self.index_term(name, false);
+        self
+    }
+
+    /// Remove everything, including protected terms and configuration, like assigning
+    /// `Context::default()`, but keeping allocations where possible.
+    pub fn clear(&mut self) {
+        self.ns = None;
+        self.lang.clear();
+        self.direction = None;
+        self.base = None;
+        self.version = None;
+        self.prefixes.clear();
+        self.aliases.clear();
+        self.clear_containers();
+        self.types.clear();
+        self.directions.clear();
+        self.scoped.clear();
+        self.protected.clear();
+        self.unknown_prefix = UnknownPrefix::default();
+        self.previous = None;
+        self.schemes.clear();
+        self.prefix_redefinition = PrefixRedefinition::default();
+    }
+
+    /// Remove all CURIE prefixes, including those of protected terms.
+    pub fn clear_prefixes(&mut self) {
+        self.prefixes.clear();
+    }
+
+    /// Remove all aliases, including those of protected terms.
+    pub fn clear_aliases(&mut self) {
+        self.aliases.clear();
+    }
+
+    /// Remove all container mappings, including `@set`, and including those of protected terms.
+    pub fn clear_containers(&mut self) {
+        self.container.clear();
+        self.sets.clear();
+    }
+
+    /// The default namespace, if defined.
+    pub fn vocab(&self) -> Option<&str> {
+        self.ns.as_deref()
+    }
+
+    /// The base IRI, if defined.
+    pub fn base(&self) -> Option<&str> {
+        self.base.as_deref()
+    }
+
+    /// The default language, if defined.
+    pub fn language(&self) -> Option<&str> {
+        Some(self.lang.as_str()).filter(|lang| !lang.is_empty())
+    }
+
+    /// The default base direction, if defined.
+    pub fn direction(&self) -> Option<&str> {
+        self.direction.as_deref()
+    }
+
+    /// The base direction for strings of a term, which is the default unless the term defines
+    /// its own.
+    pub fn direction_of(&self, name: &str) -> Option<&str> {
+        match self.directions.get(name) {
+            Some(direction) => Some(direction.as_str()).filter(|direction| !direction.is_empty()),
+            None => self.direction(),
+        }
+    }
+
+    /// The declared processing mode, if any.
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    /// The base IRI of a CURIE prefix, if defined.
+    pub fn prefix(&self, name: &str) -> Option<&str> {
+        self.prefixes.get(name).map(String::as_str)
+    }
+
+    /// Find the names that expand to an absolute IRI, for example to suggest rules for a
+    /// `TargetContext`.
+    ///
+    /// The result contains terms aliased to exactly the IRI, followed by CURIE prefixes with a
+    /// base IRI the IRI starts with, followed by the empty string if the IRI is in the default
+    /// namespace, like the empty prefix of a `TargetContext` rule.
+    pub fn terms_for(&self, iri: &str) -> Vec<&str> {
+        let aliases = self.aliases.keys()
+            .filter(|term| self.expand_type(term).is_some_and(|expanded| expanded == iri));
+        let prefixes = self.prefixes.iter()
+            .filter(|&(_, base)| iri.starts_with(base.as_str()))
+            .map(|(prefix, _)| prefix);
+        let vocab = self.ns.iter()
+            .filter(|ns| iri.starts_with(ns.as_str()))
+            .map(|_| "");
+        aliases.chain(prefixes).map(String::as_str).chain(vocab).collect()
+    }
+
+    /// The number of definitions in this context.
+    ///
+    /// Each of `@vocab`, `@base`, `@language`, `@direction` and `@version` counts as one definition
+    /// if set, and each entry in the maps and sets of term definitions counts as one. A term with
+    /// both a prefix and a container mapping, for example, counts as two, and the container mapping
+    /// recorded under the absolute IRI of the term counts separately. Protection of terms, and
+    /// the `unknown_prefix` and `schemes` configuration are not counted.
+    pub fn len(&self) -> usize {
+        let defaults = [self.ns.is_some(), self.base.is_some(), !self.lang.is_empty(),
+            self.direction.is_some(), self.version.is_some()];
+        defaults.iter().filter(|&&set| set).count()
+            + self.prefixes.len()
+            + self.aliases.len()
+            + self.container.len()
+            + self.sets.len()
+            + self.types.len()
+            + self.directions.len()
+            + self.scoped.len()
+    }
+
+    /// Whether this context defines nothing, as counted by `len`.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the defined CURIE prefixes and their base IRIs, ordered by prefix.
+    pub fn iter_prefixes(&self) -> Prefixes<'_> {
+        Prefixes(self.prefixes.iter())
+    }
+
+    /// The name a term is an alias of, if defined.
+    pub fn alias(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name).map(String::as_str)
+    }
+
+    /// The container mapping of a term, if defined.
+    pub fn container_of(&self, name: &str) -> Option<&str> {
+        self.container.get(name).map(String::as_str)
+    }
+
     /// Merge an `@context` value into this structure.
+    ///
+    /// Remote context references are ignored. Use `merge_value_with` to resolve them.
     pub fn merge_value(&mut self, value: &Value) {
+        self.merge_value_inner(value, &mut Resolver::default())
+            .expect("lenient merge failed");
+    }
+
+    /// Parse an `@context` value from JSON text, and merge it into this structure.
+    ///
+    /// Remote context references are ignored, like in `merge_value`. On a parse error, the
+    /// context is left unchanged.
+    pub fn merge_str(&mut self, input: &str) -> json::Result<()> {
+        let value: Value = json::from_str(input)?;
+        self.merge_value(&value);
+        Ok(())
+    }
+
+    /// Merge an `@context` value into this structure, resolving remote context references using
+    /// the given loader.
+    ///
+    /// Remote contexts that fail to load are ignored.
+    pub fn merge_value_with(&mut self, value: &Value, loader: &dyn ContextLoader) {
+        let mut resolver = Resolver { loader: Some(loader), ..Resolver::default() };
+        self.merge_value_inner(value, &mut resolver)
+            .expect("lenient merge failed");
+    }
+
+    /// Merge an `@context` value, using the given resolver.
+    fn merge_value_inner(&mut self, value: &Value, resolver: &mut Resolver)
+        -> Result<(), JsonNsError>
+    {
         for value in OneOrMany::from(value) {
             match *value {
                 Value::Null => {
-                    // A null clears the context.
-                    *self = Context::default();
+                    // A null clears the context, including anything inherited from an outer
+                    // scope. Elements following it in an array are merged into the empty context.
+                    // Protected terms survive this, or cause an error in strict mode.
+                    if let Some(term) = self.protected.iter().next().filter(|_| resolver.strict) {
+                        return Err(JsonNsError::ProtectedTerm(term.clone()));
+                    }
+                    self.reset();
                 },
                 Value::Object(ref object) => {
                     // An object is merged into the context.
-                    self.merge_object(object);
+                    self.merge_object_inner(object, resolver)?;
+                },
+                Value::String(ref iri) => {
+                    // A remote context reference.
+                    self.merge_remote(iri, resolver)?;
                 },
                 _ => {
-                    // Captures remote context references, but also anything else we don't understand.
-                    // These are simply ignored.
+                    // Anything else we don't understand is simply ignored.
                 },
             }
         }
+        Ok(())
+    }
+
+    /// Merge a remote context, using the given resolver.
+    ///
+    /// The remote document should contain an `@context` property, which is merged in turn.
+    /// Without a loader, the reference is ignored, as is a reference back to a context that is
+    /// still being merged.
+    fn merge_remote(&mut self, iri: &str, resolver: &mut Resolver) -> Result<(), JsonNsError> {
+        if resolver.loading.iter().any(|loading| loading == iri) {
+            if resolver.strict {
+                return Err(JsonNsError::CyclicContext(iri.to_owned()));
+            }
+            return Ok(());
+        }
+        if resolver.loader.is_some() {
+            resolver.loaded += 1;
+            if resolver.max_loaded.is_some_and(|max| resolver.loaded > max) {
+                if resolver.strict {
+                    return Err(JsonNsError::LimitExceeded(Limit::Contexts));
+                }
+                return Ok(());
+            }
+        }
+        let remote = match resolver.loader.map(|loader| loader.load(iri)) {
+            Some(Ok(remote)) => remote,
+            Some(Err(error)) if resolver.strict => {
+                return Err(JsonNsError::Loader { iri: iri.to_owned(), error });
+            },
+            _ => return Ok(()),
+        };
+        if let Some(context) = remote.get("@context") {
+            resolver.loading.push(iri.to_owned());
+            let result = self.merge_value_inner(context, resolver);
+            resolver.loading.pop();
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Check an `@context` value for mistakes, without merging it.
+    ///
+    /// Processing is lenient, and silently ignores anything it doesn't understand in a context.
+    /// This method instead reports those parts, which is useful to catch mistakes early.
+    pub fn validate(value: &Value) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        validate_context(value, "", &mut warnings);
+        warnings
+    }
+
+    /// Clear all definitions, except for protected terms, and keep configuration.
+    fn reset(&mut self) {
+        let mut context = Context {
+            unknown_prefix: self.unknown_prefix,
+            schemes: mem::take(&mut self.schemes),
+            prefix_redefinition: self.prefix_redefinition,
+            ..Context::default()
+        };
+        for term in &self.protected {
+            context.set_term(term, self.term(term));
+        }
+        context.protected = mem::take(&mut self.protected);
+        *self = context;
     }
 
     /// Merge an `@context` object into this structure.
     pub fn merge_object(&mut self, object: &Map) {
+        self.merge_object_inner(object, &mut Resolver::default())
+            .expect("lenient merge failed");
+    }
+
+    /// Merge the definitions of another context into this structure.
+    ///
+    /// This is like merging the JSON the other context was created from: everything that is set in
+    /// `other` replaces what is set in this context, for defaults as well as for each part of a
+    /// term definition. Protected terms of this context are kept as-is, and protected terms of
+    /// `other` become protected. Prefixes are kept if required by `prefix_redefinition`. The
+    /// configuration of this context is not changed.
+    pub fn merge(&mut self, other: &Context) {
+        if other.ns.is_some() {
+            self.ns = other.ns.clone();
+        }
+        if !other.lang.is_empty() {
+            self.lang = other.lang.clone();
+        }
+        if other.direction.is_some() {
+            self.direction = other.direction.clone();
+        }
+        if other.base.is_some() {
+            self.base = other.base.clone();
+        }
+        if other.version.is_some() {
+            self.version = other.version.clone();
+        }
+
+        let terms: BTreeSet<&String> = other.prefixes.keys()
+            .chain(other.aliases.keys())
+            .chain(other.container.keys())
+            .chain(other.sets.iter())
+            .chain(other.types.keys())
+            .chain(other.directions.keys())
+            .chain(other.scoped.keys())
+            .filter(|key| !self.protected.contains(*key))
+            .collect();
+        for key in terms {
+            let previous = self.term(key);
+            let mut term = other.term(key);
+            if self.prefix_redefinition == PrefixRedefinition::Keep && previous.prefix.is_some() {
+                term.prefix = None;
+            }
+            self.set_term(key, Term {
+                prefix: term.prefix.or(previous.prefix),
+                alias: term.alias.or(previous.alias),
+                container: term.container.or(previous.container),
+                set: term.set || previous.set,
+                ty: term.ty.or(previous.ty),
+                direction: term.direction.or(previous.direction),
+                scoped: term.scoped.or(previous.scoped),
+            });
+            self.index_term(key, false);
+        }
+        self.protected.extend(other.protected.iter().cloned());
+    }
+
+    /// The definitions in this context that are not present in `base`, or that differ from it.
+    ///
+    /// This shows what a context contributes over the one it was derived from. Each part of a
+    /// term definition is compared separately. Definitions that were cleared compared to `base`
+    /// can't be represented, and are not included. The result keeps the `unknown_prefix` and
+    /// `prefix_redefinition` settings of this context.
+    pub fn difference(&self, base: &Context) -> Context {
+        Context {
+            ns: option_difference(&self.ns, &base.ns),
+            lang: if self.lang != base.lang { self.lang.clone() } else { String::new() },
+            direction: option_difference(&self.direction, &base.direction),
+            base: option_difference(&self.base, &base.base),
+            version: option_difference(&self.version, &base.version),
+            prefixes: map_difference(&self.prefixes, &base.prefixes),
+            aliases: map_difference(&self.aliases, &base.aliases),
+            container: map_difference(&self.container, &base.container),
+            sets: self.sets.difference(&base.sets).cloned().collect(),
+            types: map_difference(&self.types, &base.types),
+            directions: map_difference(&self.directions, &base.directions),
+            scoped: map_difference(&self.scoped, &base.scoped),
+            protected: self.protected.difference(&base.protected).cloned().collect(),
+            unknown_prefix: self.unknown_prefix,
+            previous: None,
+            schemes: self.schemes.difference(&base.schemes).cloned().collect(),
+            prefix_redefinition: self.prefix_redefinition,
+        }
+    }
+
+    /// Merge an `@context` object, using the given resolver.
+    fn merge_object_inner(&mut self, object: &Map, resolver: &mut Resolver)
+        -> Result<(), JsonNsError>
+    {
+        // A non-propagating context remembers what to return to in nested nodes.
+        if object.get("@propagate") == Some(&Value::Bool(false)) && self.previous.is_none() {
+            self.previous = Some(Box::new(self.clone()));
+        }
+
+        // Whether to refuse features of JSON-LD 1.1, because the version was not declared, either
+        // earlier or in this object.
+        let unversioned = resolver.strict && resolver.require_version && self.version.is_none()
+            && !object.get("@version").is_some_and(is_version);
+
+        // An imported context is merged first, so local definitions override it.
+        if let Some(iri) = object.get("@import").and_then(Value::as_str) {
+            if unversioned {
+                return Err(JsonNsError::VersionRequired("@import".to_owned()));
+            }
+            self.merge_remote(iri, resolver)?;
+        }
+
+        // Whether terms in this object are protected by default.
+        let protect = object.get("@protected").and_then(Value::as_bool).unwrap_or(false);
+
+        // Set the default namespace. May be null to clear it. This is done before term
+        // definitions, which may be relative to it, but it may use a prefix defined in the same
+        // object.
+        if let Some(value) = object.get("@vocab") {
+            let ns = value.as_str().and_then(|s| self.resolve_vocab(object, s));
+            if ns.is_some() || value.is_null() {
+                self.ns = ns;
+            }
+        }
+
         for (key, value) in object {
             if is_keyword(key) {
                 match key.as_str() {
-                    "@vocab" => {
-                        // Set the default namespace. May be null to clear it.
-                        if let Some(ns) = value.as_str().filter(|s| is_absolute_iri(s)) {
-                            self.ns = Some(ns.to_owned());
-                        } else if value.is_null() {
-                            self.ns = None;
+                    "@base" => {
+                        // Set the base IRI. May be null to clear it, or relative to the current
+                        // base IRI.
+                        match (value, &self.base) {
+                            (Value::String(iri), _) if is_absolute_iri(iri) => {
+                                self.base = Some(iri.clone());
+                            },
+                            (Value::String(iri), Some(base)) => {
+                                self.base = Some(iri::resolve(base, iri));
+                            },
+                            (Value::Null, _) => self.base = None,
+                            _ => {},
                         }
                     },
                     "@language" => {
@@ -176,52 +710,291 @@ impl Context {
                             self.lang = "".to_owned();
                         }
                     },
+                    "@version" if is_version(value) => {
+                        // Record the declared processing mode. Other values are ignored, and
+                        // reported by `Context::validate`.
+                        self.version = Some("1.1".to_owned());
+                    },
+                    "@direction" => {
+                        // Set the default base direction. May be null to clear it.
+                        if let Some(direction) = value.as_str().filter(|s| is_direction(s)) {
+                            self.direction = Some(direction.to_owned());
+                        } else if value.is_null() {
+                            self.direction = None;
+                        }
+                    },
                     _ => {},
                 }
+                continue;
+            }
+
+            // A scoped context is one of the features of JSON-LD 1.1.
+            if unversioned && value.get("@context").is_some() {
+                return Err(JsonNsError::VersionRequired("@context".to_owned()));
+            }
+
+            let cleared = value.is_null() && !self.protected.contains(key);
+            if self.protected.contains(key) {
+                // A protected term may only be redefined with an identical definition. Otherwise,
+                // the original definition is kept.
+                let previous = self.term(key);
+                self.merge_term(key, value);
+                if self.term(key) != previous {
+                    self.set_term(key, previous);
+                    if resolver.strict {
+                        return Err(JsonNsError::ProtectedTerm(key.clone()));
+                    }
+                }
             } else {
-                match *value {
-                    Value::String(ref string) => {
-                        // Define a namespace.
-                        if is_curie_prefix(key) && is_absolute_iri(string) {
-                            self.prefixes.insert(key.to_owned(), string.to_owned());
-                        }
-                    },
-                    Value::Object(ref object) => {
-                        // Look for an alias.
-                        let alias = object.get("@id")
+                let previous = self.prefixes.get(key).cloned();
+                self.merge_term(key, value);
+                if let Some(previous) = previous.filter(|base| self.is_redefined(key, base)) {
+                    // Restore the earlier prefix, but keep the rest of the new definition.
+                    self.prefixes.insert(key.clone(), previous);
+                    if resolver.strict {
+                        return Err(JsonNsError::PrefixRedefined(key.clone()));
+                    }
+                }
+                let protect = value.get("@protected").and_then(Value::as_bool).unwrap_or(protect);
+                if protect && !value.is_null() {
+                    self.protected.insert(key.clone());
+                }
+            }
+            self.index_term(key, cleared);
+        }
+
+        Ok(())
+    }
+
+    /// Resolve an `@vocab` value, which may be a CURIE using a prefix defined in the given object.
+    fn resolve_vocab(&self, object: &Map, vocab: &str) -> Option<String> {
+        if !is_absolute_iri(vocab) {
+            return None;
+        }
+
+        let (prefix, suffix) = vocab.split_at(vocab.find(':')?);
+        let base = match object.get(prefix) {
+            Some(Value::String(base)) => Some(base.as_str()),
+            Some(Value::Object(definition)) if definition.get("@prefix") == Some(&true.into()) => {
+                definition.get("@id").and_then(Value::as_str)
+            },
+            _ => None,
+        };
+        match base.filter(|base| is_curie_prefix(prefix) && is_absolute_iri(base)) {
+            Some(base) => Some(format!("{}{}", base, &suffix[1..])),
+            None => self.expand_name(vocab).map(Cow::into_owned),
+        }
+    }
+
+    /// Merge a single term definition into this structure.
+    fn merge_term(&mut self, key: &str, value: &Value) {
+        match *value {
+            Value::String(ref string) if is_curie_prefix(key) && is_absolute_iri(string) => {
+                // Define a namespace.
+                self.prefixes.insert(key.to_owned(), string.to_owned());
+            },
+            Value::Object(ref object) => {
+                // Look for an alias. This may also alias a keyword, except for `@context`. A
+                // relative name is resolved against the default namespace, if there is one.
+                let alias = object.get("@id")
+                    .and_then(Value::as_str)
+                    .filter(|string| *string != "@context");
+                if let Some(alias) = alias {
+                    let alias = match self.ns {
+                        Some(ref ns) if !alias.contains(':') && !is_keyword(alias) => {
+                            format!("{}{}", ns, alias)
+                        },
+                        _ => alias.to_owned(),
+                    };
+                    self.aliases.insert(key.to_owned(), alias);
+                }
+
+                // Look for a prefix flag. When set, the term can also be used as a CURIE prefix.
+                // When unset, this clears a prefix defined earlier for the term.
+                match object.get("@prefix").and_then(Value::as_bool) {
+                    Some(true) => {
+                        let base = object.get("@id")
                             .and_then(Value::as_str)
-                            .filter(|string| !is_keyword(string));
-                        if let Some(alias) = alias {
-                            self.aliases.insert(key.to_owned(), alias.to_owned());
+                            .filter(|string| is_curie_prefix(key) && is_absolute_iri(string));
+                        if let Some(base) = base {
+                            self.prefixes.insert(key.to_owned(), base.to_owned());
                         }
+                    },
+                    Some(false) => {
+                        self.prefixes.remove(key);
+                    },
+                    None => {},
+                }
 
-                        // Look for a container mapping.
-                        let container = object.get("@container")
-                            .and_then(Value::as_str);
-                        if let Some(container) = container {
+                // Look for a container mapping. This may be an array, to combine `@set` with
+                // another container.
+                if let Some(value) = object.get("@container") {
+                    for container in OneOrMany::from(value).filter_map(Value::as_str) {
+                        if container == "@set" {
+                            self.sets.insert(key.to_owned());
+                        } else {
                             self.container.insert(key.to_owned(), container.to_owned());
                         }
+                    }
+                }
+
+                // Look for a type mapping.
+                let ty = object.get("@type")
+                    .and_then(Value::as_str);
+                if let Some(ty) = ty {
+                    self.types.insert(key.to_owned(), ty.to_owned());
+                }
+
+                // Look for a base direction. Null means no direction, even if there is a default.
+                match object.get("@direction") {
+                    Some(Value::String(direction)) if is_direction(direction) => {
+                        self.directions.insert(key.to_owned(), direction.to_owned());
                     },
-                    Value::Null => {
-                        // A null value is used to clear whatever was defined.
-                        self.prefixes.remove(key);
-                        self.aliases.remove(key);
-                        self.container.remove(key);
+                    Some(Value::Null) => {
+                        self.directions.insert(key.to_owned(), String::new());
                     },
                     _ => {},
                 }
-            }
+
+                // Look for a scoped context.
+                if let Some(context) = object.get("@context") {
+                    self.scoped.insert(key.to_owned(), context.clone());
+                }
+            },
+            Value::Null => {
+                // A null value is used to clear whatever was defined.
+                self.set_term(key, Term::default());
+            },
+            _ => {},
+        }
+    }
+
+    /// Record the container mapping and `@set` flag of a term under the absolute IRI it expands
+    /// to as well, or remove them there if the term was cleared.
+    fn index_term(&mut self, key: &str, cleared: bool) {
+        let iri = match self.expand_type(key) {
+            Some(iri) if iri != key => iri.into_owned(),
+            _ => return,
+        };
+        if cleared {
+            self.container.remove(&iri);
+            self.sets.remove(&iri);
+            return;
+        }
+        if let Some(container) = self.container.get(key).cloned() {
+            self.container.insert(iri.clone(), container);
+        }
+        if self.sets.contains(key) {
+            self.sets.insert(iri);
+        }
+    }
+
+    /// Whether a prefix defined earlier with the given base IRI was redefined with a different one,
+    /// and `prefix_redefinition` requires keeping the earlier definition.
+    fn is_redefined(&self, key: &str, previous: &str) -> bool {
+        self.prefix_redefinition == PrefixRedefinition::Keep
+            && self.prefixes.get(key).is_some_and(|base| base != previous)
+    }
+
+    /// Get a copy of everything defined for a term.
+    fn term(&self, key: &str) -> Term {
+        Term {
+            prefix: self.prefixes.get(key).cloned(),
+            alias: self.aliases.get(key).cloned(),
+            container: self.container.get(key).cloned(),
+            set: self.sets.contains(key),
+            ty: self.types.get(key).cloned(),
+            direction: self.directions.get(key).cloned(),
+            scoped: self.scoped.get(key).cloned(),
+        }
+    }
+
+    /// Replace everything defined for a term.
+    fn set_term(&mut self, key: &str, term: Term) {
+        set_entry(&mut self.prefixes, key, term.prefix);
+        set_entry(&mut self.aliases, key, term.alias);
+        set_entry(&mut self.container, key, term.container);
+        if term.set {
+            self.sets.insert(key.to_owned());
+        } else {
+            self.sets.remove(key);
+        }
+        set_entry(&mut self.types, key, term.ty);
+        set_entry(&mut self.directions, key, term.direction);
+        set_entry(&mut self.scoped, key, term.scoped);
+    }
+
+    /// Resolve a property name according to this context.
+    ///
+    /// Unlike `expand_name`, this first looks up aliases, and distinguishes keywords (including
+    /// terms aliased to keywords) from names that should be dropped.
+    pub fn resolve_name<'a>(&'a self, name: &'a str) -> Name<'a> {
+        let name = self.aliases.get(name).map(String::as_str).unwrap_or(name);
+        if is_keyword(name) {
+            return Name::Keyword(name);
+        }
+        match self.expand_name(name) {
+            Some(iri) => Name::Iri(iri),
+            None => Name::Drop,
+        }
+    }
+
+    /// Expand a type name, which may be an alias, according to this context.
+    fn expand_type<'a>(&'a self, name: &'a str) -> Option<Cow<'a, str>> {
+        match self.resolve_name(name) {
+            Name::Iri(iri) => Some(iri),
+            Name::Keyword(_) | Name::Drop => None,
         }
     }
 
+    /// Expand the value of an `@id` according to this context.
+    ///
+    /// A CURIE is expanded like a name, but a relative reference is resolved against the base IRI
+    /// instead of the default namespace. Returns `None` if the result is not an absolute IRI.
+    fn expand_id<'a>(&self, id: &'a str, base: Option<&str>) -> Option<Cow<'a, str>> {
+        if is_absolute_iri(id) {
+            // An `@id` is never relative to the default namespace.
+            self.expand_prefixed(id, false)
+        } else if is_keyword(id) {
+            None
+        } else {
+            base.map(|base| Cow::from(iri::resolve(base, id)))
+        }
+    }
+
+    /// Find the container mapping of a property by its literal name, or otherwise of a term that
+    /// expands to the same absolute IRI.
+    fn container_for(&self, key: &str, iri: &str) -> Option<&str> {
+        self.container.get(key)
+            .or_else(|| self.container.get(iri))
+            .map(String::as_str)
+    }
+
+    /// Whether a property has a `@set` container, by its literal name, or otherwise by a term that
+    /// expands to the same absolute IRI.
+    fn is_set(&self, key: &str, iri: &str) -> bool {
+        self.sets.contains(key) || self.sets.contains(iri)
+    }
+
     /// Expand a name according to this context.
     ///
     /// A name may be an absolute IRI, a CURIE within a defined namespace, or a name in the default
     /// namespace, otherwise `None` is returned (and the property or value should be dropped).
+    /// Blank node identifiers, which start with `_:`, are returned unchanged.
     pub fn expand_name<'a>(&self, name: &'a str) -> Option<Cow<'a, str>> {
         if name.starts_with('@') {
             return None;
         }
+        self.expand_prefixed(name, true)
+    }
+
+    /// Expand a name that is not a keyword. Unless `vocab` is set, `UnknownPrefix::Vocab` never
+    /// expands a name with an unknown prefix in the default namespace, and acts like
+    /// `UnknownPrefix::Drop` instead.
+    fn expand_prefixed<'a>(&self, name: &'a str, vocab: bool) -> Option<Cow<'a, str>> {
+        if is_blank_node(name) {
+            return Some(Cow::from(name));
+        }
 
         let mut parts = name.splitn(2, ':');
         let prefix = parts.next().unwrap();
@@ -229,17 +1002,32 @@ impl Context {
             if let Some(base) = self.prefixes.get(prefix) {
                 // A CURIE within a defined namespace.
                 Some(Cow::from(format!("{}{}", base, suffix)))
-            } else {
+            } else if self.unknown_prefix == UnknownPrefix::Iri || self.is_known_scheme(prefix) {
                 // An absolute IRI in some other scheme.
                 Some(Cow::from(name))
+            } else if vocab && self.unknown_prefix == UnknownPrefix::Vocab {
+                // Likely a term that happens to contain a colon.
+                self.expand_vocab(name)
+            } else {
+                // Likely a CURIE with an undefined prefix.
+                None
             }
-        } else if let Some(ref base) = self.ns {
-            // A term in the default namespace.
-            Some(Cow::from(format!("{}{}", base, name)))
         } else {
-            None
+            // A term in the default namespace.
+            self.expand_vocab(name)
         }
     }
+
+    /// Expand a term in the default namespace, if there is one.
+    fn expand_vocab<'a>(&self, name: &'a str) -> Option<Cow<'a, str>> {
+        self.ns.as_ref().map(|base| Cow::from(format!("{}{}", base, name)))
+    }
+
+    /// Whether the input is a well-known URI scheme, or one listed in `schemes`.
+    fn is_known_scheme(&self, input: &str) -> bool {
+        let scheme = input.to_ascii_lowercase();
+        KNOWN_SCHEMES.contains(&scheme.as_str()) || self.schemes.contains(&scheme)
+    }
 }
 
 impl<'a> From<&'a Value> for Context {
@@ -258,16 +1046,65 @@ impl<'a> From<&'a Map> for Context {
     }
 }
 
-/// Structure holding the target context to reword a document to.
-///
-/// An instance of this struct is part of the `Processor`, which can be modified to provide rules
-/// according to which the output will be reworded.
-///
-/// By default, this context is empty, which will result in an output document containing only
-/// absolute IRIs.
-#[derive(Clone,Debug,Default)]
-pub struct TargetContext {
-    /// Pairs of CURIE prefixes and their respective base IRIs.
+impl<'a> IntoIterator for &'a Context {
+    type Item = (&'a str, &'a str);
+    type IntoIter = Prefixes<'a>;
+
+    fn into_iter(self) -> Prefixes<'a> {
+        self.iter_prefixes()
+    }
+}
+
+/// Iterator over the prefixes of a `Context`, created by `Context::iter_prefixes`.
+#[derive(Clone,Debug)]
+pub struct Prefixes<'a>(btree_map::Iter<'a, String, String>);
+
+impl<'a> Iterator for Prefixes<'a> {
+    type Item = (&'a str, &'a str);
+
+    fn next(&mut self) -> Option<(&'a str, &'a str)> {
+        self.0.next().map(|(prefix, base)| (prefix.as_str(), base.as_str()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+/// The result of resolving a property name using `Context::resolve_name`.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Name<'a> {
+    /// The name is a keyword, or a term aliased to a keyword.
+    Keyword(&'a str),
+    /// The name expands to an absolute IRI.
+    Iri(Cow<'a, str>),
+    /// The name doesn't expand, and the property should be dropped.
+    Drop,
+}
+
+/// A source of remote contexts.
+///
+/// Documents may reference contexts by IRI, for example `"@context": "http://example.com/ctx"`.
+/// By default, such references are ignored, but a loader can be set on the `Processor` to resolve
+/// them. The crate provides `HttpLoader` when the `http` feature is enabled.
+pub trait ContextLoader: Debug + Send + Sync {
+    /// Load the document at the given IRI.
+    ///
+    /// The document should contain an `@context` property, which is then merged into the active
+    /// context. Any error causes the reference to be ignored.
+    fn load(&self, iri: &str) -> io::Result<Value>;
+}
+
+/// Structure holding the target context to reword a document to.
+///
+/// An instance of this struct is part of the `Processor`, which can be modified to provide rules
+/// according to which the output will be reworded.
+///
+/// By default, this context is empty, which will result in an output document containing only
+/// absolute IRIs.
+#[derive(Clone,Debug,Default)]
+pub struct TargetContext {
+    /// Pairs of CURIE prefixes and their respective base IRIs.
     ///
     /// For absolute IRIs that are about to be added to the output document, the processor will try
     /// to find a matching prefix in this list. If found, a CURIE will be used instead.
@@ -275,6 +1112,28 @@ pub struct TargetContext {
     /// This list may also contain an entry with an empty string prefix, which then represents the
     /// default namespace of the output document.
     pub rules: Vec<(String, String)>,
+    /// How to choose between multiple matching rules. Defaults to `CompactStrategy::FirstMatch`.
+    pub strategy: CompactStrategy,
+    /// Whether to normalize percent-encoding in IRIs and rule bases before matching. Defaults to
+    /// `false`.
+    ///
+    /// Escapes of unreserved characters, such as `%7E` for `~`, are decoded, and the hexadecimal
+    /// digits of other escapes are uppercased. Escapes of reserved characters, such as `%23` for
+    /// `#`, are kept, because they are not equivalent to the character itself.
+    pub normalize_percent: bool,
+    /// Map of absolute IRIs to terms, used by `compact_name` before the rules.
+    pub terms: BTreeMap<String, String>,
+}
+
+/// How `TargetContext::compact_iri` chooses between multiple rules matching an IRI.
+#[derive(Clone,Copy,Debug,Default,PartialEq,Eq,Hash)]
+pub enum CompactStrategy {
+    /// Use the first matching rule, in the order of `TargetContext::rules`.
+    #[default]
+    FirstMatch,
+    /// Use the rule with the empty prefix if it matches, so the default namespace produces plain
+    /// names even where a CURIE prefix also matches. Otherwise, use the first matching rule.
+    PreferDefault,
 }
 
 impl TargetContext {
@@ -289,11 +1148,81 @@ impl TargetContext {
         self
     }
 
+    /// A short-hand for adding a term for `compact_name`.
+    pub fn add_term(&mut self, term: &str, iri: &str) -> &mut Self {
+        self.terms.insert(iri.to_owned(), term.to_owned());
+        self
+    }
+
+    /// Remove all rules with the given prefix, returning how many were removed.
+    pub fn remove_rule(&mut self, prefix: &str) -> usize {
+        let len = self.rules.len();
+        self.rules.retain(|(other, _)| other != prefix);
+        len - self.rules.len()
+    }
+
+    /// Remove all rules.
+    pub fn clear_rules(&mut self) {
+        self.rules.clear();
+    }
+
+    /// Check the rules for mistakes.
+    ///
+    /// This reports rules with a base that is not an absolute IRI, which never match, and rules
+    /// with a prefix that can't be used in a CURIE, or that repeats the prefix of an earlier rule,
+    /// which is then never used. The empty prefix for the default namespace is allowed once.
+    pub fn validate(&self) -> Vec<Warning> {
+        let mut warnings = Vec::new();
+        let mut seen = HashSet::new();
+        for (index, (prefix, base)) in self.rules.iter().enumerate() {
+            if !prefix.is_empty() && !is_curie_prefix(prefix) {
+                warnings.push(Warning::InvalidDefinition(format!("/{}/0", index)));
+            } else if !seen.insert(prefix) {
+                warnings.push(Warning::DuplicatePrefix(format!("/{}/0", index)));
+            }
+            if !is_absolute_iri(base) {
+                warnings.push(Warning::InvalidIri(format!("/{}/1", index)));
+            }
+        }
+        warnings
+    }
+
     /// Compact an absolute IRI according to this context.
     pub fn compact_iri<'a>(&self, iri: &'a str) -> Cow<'a, str> {
+        if self.normalize_percent {
+            if let Cow::Owned(iri) = normalize_percent(iri) {
+                return Cow::from(self.match_rules(&iri).into_owned());
+            }
+        }
+        self.match_rules(iri)
+    }
+
+    /// Compact an absolute IRI according to this context, preferring a term from `terms`.
+    ///
+    /// Unlike `compact_iri`, this can produce a term for an IRI that is not covered by any rule.
+    pub fn compact_name<'a>(&'a self, iri: &'a str) -> Cow<'a, str> {
+        match self.terms.get(iri) {
+            Some(term) => Cow::from(term.as_str()),
+            None => self.compact_iri(iri),
+        }
+    }
+
+    /// Compact an absolute IRI using the first matching rule, according to `strategy`.
+    fn match_rules<'a>(&self, iri: &'a str) -> Cow<'a, str> {
+        let suffix = |base: &str| {
+            let base = if self.normalize_percent { normalize_percent(base) } else { Cow::from(base) };
+            iri.strip_prefix(base.as_ref())
+        };
+        if self.strategy == CompactStrategy::PreferDefault {
+            let default = self.rules.iter()
+                .filter(|(prefix, _)| prefix.is_empty())
+                .find_map(|(_, base)| suffix(base));
+            if let Some(suffix) = default {
+                return Cow::from(suffix);
+            }
+        }
         for (prefix, base) in &self.rules {
-            if iri.starts_with(base) {
-                let suffix = &iri[base.len()..];
+            if let Some(suffix) = suffix(base) {
                 if prefix.is_empty() {
                     // Matched the default namespace.
                     return Cow::from(suffix);
@@ -308,6 +1237,18 @@ impl TargetContext {
     }
 }
 
+impl FromIterator<(String, String)> for TargetContext {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> TargetContext {
+        TargetContext { rules: iter.into_iter().collect(), ..TargetContext::default() }
+    }
+}
+
+impl Extend<(String, String)> for TargetContext {
+    fn extend<I: IntoIterator<Item = (String, String)>>(&mut self, iter: I) {
+        self.rules.extend(iter);
+    }
+}
+
 /// A document processor.
 ///
 /// This structure holds configuration for processing documents. The defaults are fine if the
@@ -321,6 +1262,354 @@ pub struct Processor {
     /// Target context to reword the document to. Defaults to an empty context, so the result will
     /// contain only absolute IRIs for all properties and types.
     pub target: TargetContext,
+    /// Loader used to resolve remote context references. Defaults to `None`, in which case remote
+    /// contexts are ignored.
+    pub loader: Option<Arc<dyn ContextLoader>>,
+    /// Maximum number of properties to process across the whole document, including keywords.
+    /// Defaults to `None`, which means no limit.
+    ///
+    /// When the limit is reached, `process_value` silently skips the remaining properties, while
+    /// `process_value_strict` returns an error.
+    pub max_properties: Option<usize>,
+    /// Maximum nesting depth of arrays and objects in a document. Defaults to `None`, which means
+    /// no limit.
+    ///
+    /// Processing recurses into nested values, so very deep nesting can exhaust the stack. Parsing
+    /// with `serde_json` already limits nesting to 128 levels, but values built in other ways may
+    /// be deeper. When the limit is reached, `process_value` replaces deeper arrays and objects
+    /// with empty ones, while `process_value_strict` returns an error.
+    pub max_depth: Option<usize>,
+    /// Maximum number of remote contexts to load while processing a document, including imports.
+    /// Defaults to `None`, which means no limit.
+    ///
+    /// When the limit is reached, `process_value` ignores further remote contexts, while
+    /// `process_value_strict` returns an error.
+    pub max_contexts: Option<usize>,
+    /// Maximum number of JSON values in the output, counting every object, array, string, number,
+    /// boolean and null. Defaults to `None`, which means no limit.
+    ///
+    /// Unlike `max_properties`, this accounts for values added during processing, such as language
+    /// maps and arrays. When the limit is reached, `process_value` stops adding properties, so the
+    /// output may exceed the limit by the last property added, while `process_value_strict`
+    /// returns an error.
+    pub max_output_nodes: Option<usize>,
+    /// Whether to memoize compacted names during a processing run. Defaults to `false`.
+    ///
+    /// Documents with many similar records repeat the same property names and types many times.
+    /// With this enabled, the target context rules are matched only once for each distinct IRI,
+    /// which helps with many rules. This does not reduce allocations, because every output name
+    /// is still a separate `String`, and the first use of each IRI also stores a copy.
+    pub memoize_names: bool,
+    /// Whether `flatten` should relabel blank node identifiers found in the input. Defaults to
+    /// `false`.
+    ///
+    /// When set, each `_:`-prefixed identifier is consistently replaced with a generated label,
+    /// just like the labels generated for nodes without an `@id`.
+    pub relabel_blank_nodes: bool,
+    /// Whether to allow arrays of strings in language maps. Defaults to `false`.
+    ///
+    /// Normally, only string values are kept in a language map. With this enabled, arrays are kept
+    /// as well, with only their string elements. Empty arrays are still dropped.
+    pub language_arrays: bool,
+    /// Whether to wrap all plain string property values in language maps. Defaults to `false`.
+    ///
+    /// With this enabled, a string is keyed by the default language of the active context, or
+    /// `@none` if there is none. Only strings are affected, not other values or strings in arrays.
+    pub wrap_strings: bool,
+    /// The property to record the keys of `@index` containers under. Defaults to `None`, which
+    /// drops the keys.
+    ///
+    /// When set, each value of an index map gets a property with its key, where plain values are
+    /// first wrapped in a value object. Values under `@none` have no index, and are left as-is. A
+    /// common choice is `@index`, which is also kept when processing value objects.
+    pub index_key: Option<String>,
+    /// Whether to remove leading and trailing whitespace from string values. Defaults to `false`.
+    ///
+    /// This applies to plain property values, `@value` in value objects and values in language
+    /// maps, before `value_transform`. Names, `@id` and `@type` are not affected.
+    pub trim_strings: bool,
+    /// The language map key for strings without a language, when the active context has no
+    /// default language. Defaults to `None`, which uses the empty string for a string value of a
+    /// `@language` container, and `@none` for `wrap_strings`. A common choice is `und`, the
+    /// language tag for an undetermined language.
+    pub untagged_key: Option<String>,
+    /// Settings for generating prefixes for IRIs not matched by the target context. Defaults to
+    /// `None`, which leaves such IRIs as-is.
+    ///
+    /// When set, the generated prefixes are added to an `@context` in the output, so that it can be
+    /// interpreted again. If the output is not an object, it is wrapped in a `@graph` object. For
+    /// the same reason, the `@context` also defines properties with `@type: @id` coercion, because
+    /// their node references are output as plain strings.
+    pub auto_prefix: Option<AutoPrefix>,
+    /// Whether to compact node types to alias terms of the active context. Defaults to `false`.
+    ///
+    /// When set, a type IRI is output as a term that is an alias for it in the active context, for
+    /// example `Person` for a term defined as `{ "@id": "http://schema.org/Person" }`. Only types
+    /// without such an alias are compacted using the target context.
+    pub type_aliases: bool,
+    /// Whether to order keys in output objects, with `@context`, `@id` and `@type` first, then
+    /// other keywords and properties, each sorted. Defaults to `false`.
+    ///
+    /// This only has effect with the `preserve_order` feature, which enables the same feature in
+    /// `serde_json`. Without it, objects are sorted maps, so keys are always sorted by their
+    /// bytes, and `@context`, `@id` and `@type` can't be moved first. Keywords then still come
+    /// before any property starting with a letter.
+    pub sort_keys: bool,
+    /// Whether to remove nodes that are not addressable from the output. Defaults to `false`.
+    ///
+    /// When set, nested nodes without an `@id`, or with a blank node identifier, are removed,
+    /// together with the property or array entry holding them. When flattening, all such nodes
+    /// are removed from the `@graph`, including the top-level node, and so are references to
+    /// them. This is lossy, and meant for publishing only the public parts of a document.
+    pub strip_blank_nodes: bool,
+    /// Function applied to plain values of properties. Defaults to `None`, which leaves values
+    /// as-is. See `with_value_transform`.
+    pub value_transform: Option<ValueTransform>,
+    /// Absolute IRIs of the properties to keep. Defaults to `None`, which keeps all properties.
+    ///
+    /// When set, other properties are dropped, at every level of the document. Keywords such as
+    /// `@id` and `@type` are not affected.
+    pub allowed_properties: Option<HashSet<String>>,
+    /// Absolute IRIs of properties to drop, at every level of the document. Defaults to empty.
+    ///
+    /// This takes precedence over `allowed_properties`.
+    pub denied_properties: HashSet<String>,
+    /// Map of output property names to replace, after compaction. Defaults to empty.
+    ///
+    /// Like properties that compact to the same name, a property renamed to the name of another
+    /// property replaces it if it comes later in the input.
+    pub rename: BTreeMap<String, String>,
+    /// Whether to lowercase compacted property names. Defaults to `None`, which leaves names as-is.
+    ///
+    /// Only names that were compacted are affected, so IRIs not matched by the target context are
+    /// kept intact. Lowercasing happens before `rename`, and like with renaming, a property whose
+    /// name becomes equal to that of another property replaces it if it comes later in the input.
+    pub lowercase: Option<Lowercase>,
+    /// When set, every output object gets a property with this name, listing the other keys of
+    /// the object in the order of the input. Defaults to `None`.
+    ///
+    /// This allows consumers to recover the order of properties, even though the output objects
+    /// are sorted without the `preserve_order` feature. Note that without that feature, the input
+    /// objects are sorted as well, by the input names. Use a name that can't clash with
+    /// properties, such as a CURIE with a prefix of your own, like `"my:order"`.
+    pub order_key: Option<String>,
+    /// Whether strict processing requires `"@version": 1.1` for features of JSON-LD 1.1. Defaults
+    /// to `false`.
+    ///
+    /// When set, `process_value_strict` returns an error for scoped contexts, `@import` and
+    /// `@nest`, unless the active context declares the version. Lenient processing is not
+    /// affected.
+    pub require_version: bool,
+}
+
+/// A function applied to values, used in `Processor::value_transform`.
+///
+/// This wraps the function, so that `Processor` can still implement `Clone` and `Debug`.
+#[derive(Clone)]
+pub struct ValueTransform(Arc<TransformFn>);
+
+/// The function type wrapped by `ValueTransform`.
+type TransformFn = dyn Fn(&str, &Value) -> Value + Send + Sync;
+
+impl ValueTransform {
+    /// Wrap a function. See `Processor::with_value_transform` for how it is called.
+    pub fn new<F>(transform: F) -> ValueTransform
+        where F: Fn(&str, &Value) -> Value + Send + Sync + 'static
+    {
+        ValueTransform(Arc::new(transform))
+    }
+}
+
+impl Debug for ValueTransform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("ValueTransform")
+    }
+}
+
+/// Settings for generating prefixes, used in `Processor::auto_prefix`.
+///
+/// An IRI is split after the last occurrence of any of the delimiters, and the part before is
+/// given a prefix name consisting of `name` followed by a number, starting from 0 for each
+/// document.
+#[derive(Clone,Debug)]
+pub struct AutoPrefix {
+    /// Characters to split IRIs at. Defaults to `#` and `/`.
+    pub delimiters: Vec<char>,
+    /// Name of the generated prefixes, before the number. Defaults to `ns`.
+    pub name: String,
+}
+
+impl Default for AutoPrefix {
+    fn default() -> AutoPrefix {
+        AutoPrefix {
+            delimiters: vec!['#', '/'],
+            name: "ns".to_owned(),
+        }
+    }
+}
+
+/// Which parts of compacted property names to lowercase, used in `Processor::lowercase`.
+#[derive(Clone,Copy,Debug,PartialEq,Eq,Hash)]
+pub enum Lowercase {
+    /// Lowercase terms, and the part after the prefix of CURIEs.
+    Local,
+    /// Lowercase terms and CURIEs entirely, including the prefix.
+    All,
+}
+
+/// State for a single processing run.
+#[derive(Debug,Default)]
+struct State<'a> {
+    /// Whether to abort on errors, instead of recovering from them.
+    strict: bool,
+    /// Number of properties processed so far.
+    properties: usize,
+    /// Number of remote contexts loaded so far.
+    contexts: usize,
+    /// Number of arrays and objects the value being processed is nested in.
+    depth: usize,
+    /// Number of values in the output so far, counted as they are added.
+    output_nodes: usize,
+    /// Memoized compacted names by absolute IRI, if `Processor::memoize_names` is set.
+    names: HashMap<String, String>,
+    /// Whether to produce expanded form.
+    expand: bool,
+    /// Whether nodes are collected in `nodes`, instead of being nested.
+    flatten: bool,
+    /// Nodes collected while flattening, in document order.
+    nodes: Vec<Map>,
+    /// Prefixes generated so far, if `Processor::auto_prefix` is set.
+    prefixes: Vec<(String, String)>,
+    /// Buffer to collect the types of a node in, before they are added to the output.
+    types: Vec<Value>,
+    /// Buffers to record key order in for `Processor::order_key`, one for each object being
+    /// processed.
+    orders: Vec<Vec<String>>,
+    /// Output names and absolute IRIs of properties with `@type: @id` coercion, if
+    /// `Processor::auto_prefix` is set.
+    coercions: Vec<(String, String)>,
+    /// Properties dropped so far, only collected in strict mode.
+    dropped: Vec<Dropped>,
+    /// Escaped JSON pointer segments of the current position in the input, only tracked in strict
+    /// mode.
+    path: Vec<String>,
+    /// Absolute IRI of the property whose value is being processed, if any.
+    property: Option<String>,
+    /// Base direction for strings of the property whose value is being processed, if any.
+    direction: Option<String>,
+    /// Base IRI to resolve relative `@id` values against, if any.
+    base: Option<String>,
+    /// Callback for every property added to the output, if any.
+    visitor: Option<Visitor<'a>>,
+}
+
+/// A callback for properties added to the output, see `Processor::process_value_visit`.
+struct Visitor<'a>(&'a mut dyn FnMut(&str, &Value));
+
+impl<'a> Debug for Visitor<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Visitor")
+    }
+}
+
+impl<'a> State<'a> {
+    fn new(strict: bool) -> State<'a> {
+        State { strict, ..State::default() }
+    }
+
+    /// Prepare for another lenient processing run, keeping allocated buffers.
+    ///
+    /// The name cache is only kept if requested, because it is only valid for the same settings.
+    fn reset(&mut self, keep_names: bool) {
+        self.strict = false;
+        self.properties = 0;
+        self.contexts = 0;
+        self.depth = 0;
+        self.output_nodes = 0;
+        if !keep_names {
+            self.names.clear();
+        }
+        self.expand = false;
+        self.flatten = false;
+        self.nodes.clear();
+        self.prefixes.clear();
+        self.coercions.clear();
+        self.dropped.clear();
+        self.path.clear();
+        self.property = None;
+        self.direction = None;
+    }
+
+    /// Add a segment to the current path, if tracking it.
+    fn enter<T: Display>(&mut self, segment: T) {
+        if self.strict {
+            self.path.push(escape_pointer(&segment.to_string()));
+        }
+    }
+
+    /// Get the current path as a JSON pointer.
+    fn pointer(&self) -> String {
+        self.path.iter().map(|segment| format!("/{}", segment)).collect()
+    }
+
+    /// Pass a property added to the output to the visitor, if any.
+    fn visit(&mut self, name: &str, value: &Value) {
+        if let Some(ref mut visitor) = self.visitor {
+            (visitor.0)(name, value);
+        }
+    }
+
+    /// Reserve a place for a node, before processing it.
+    ///
+    /// Returns `None` if not flattening. Otherwise, the node must be later passed to `add_node`.
+    fn reserve_node(&mut self) -> Option<usize> {
+        if self.flatten {
+            self.nodes.push(Map::new());
+            Some(self.nodes.len() - 1)
+        } else {
+            None
+        }
+    }
+
+    /// Insert a value that was already counted into an output object. A value it replaces is no
+    /// longer counted.
+    fn insert_output(&mut self, object: &mut Map, key: String, value: Value) {
+        if let Some(previous) = object.insert(key, value) {
+            self.uncount(value_size(&previous));
+        }
+    }
+
+    /// Stop counting values that were removed from the output again.
+    fn uncount(&mut self, nodes: usize) {
+        // Values are always counted before they are removed, so this would be a counting bug.
+        debug_assert!(nodes <= self.output_nodes, "output node count underflow");
+        self.output_nodes = self.output_nodes.saturating_sub(nodes);
+    }
+
+    /// Add a processed node to the place reserved for it.
+    ///
+    /// When flattening, the node is collected and a reference to it is returned. A node without
+    /// an `@id` gets a temporary identifier, which is replaced with a blank node identifier in
+    /// `label_blank_nodes`. Otherwise, the node itself is returned.
+    fn add_node(&mut self, slot: Option<usize>, mut node: Map) -> Map {
+        let slot = match slot {
+            Some(slot) => slot,
+            None => return node,
+        };
+
+        // The reference adds an object and a string to the output, and so may a generated `@id`.
+        self.output_nodes += if node.contains_key("@id") { 2 } else { 3 };
+
+        // Keywords never appear as an `@id` in the output, so this can't clash.
+        let id = node.entry("@id")
+            .or_insert_with(|| Value::String(format!("@{}", slot)))
+            .clone();
+        self.nodes[slot] = node;
+
+        let mut reference = Map::with_capacity(1);
+        reference.insert("@id".to_owned(), id);
+        reference
+    }
 }
 
 impl Processor {
@@ -329,6 +1618,38 @@ impl Processor {
         Processor::default()
     }
 
+    /// Create a processor with the given external context.
+    pub fn with_context(context: Context) -> Processor {
+        Processor { context, ..Processor::default() }
+    }
+
+    /// Replace the target context, returning the processor.
+    pub fn with_target(self, target: TargetContext) -> Processor {
+        Processor { target, ..self }
+    }
+
+    /// Set a function to transform values, returning the processor.
+    ///
+    /// The function is called for every string, number, boolean and null that is the value of a
+    /// property, or an element of an array that is. It receives the absolute IRI of the property
+    /// and the value, and returns the value to output instead. Values in value objects, language
+    /// maps and JSON literals are not passed to the function.
+    pub fn with_value_transform<F>(self, transform: F) -> Processor
+        where F: Fn(&str, &Value) -> Value + Send + Sync + 'static
+    {
+        Processor { value_transform: Some(ValueTransform::new(transform)), ..self }
+    }
+
+    /// The rules of the contained `TargetContext`.
+    pub fn rules(&self) -> &[(String, String)] {
+        &self.target.rules
+    }
+
+    /// The rules of the contained `TargetContext`, for modification.
+    pub fn rules_mut(&mut self) -> &mut Vec<(String, String)> {
+        &mut self.target.rules
+    }
+
     /// A short-hand for adding a rule to the contained `TargetContext`.
     pub fn add_rule(&mut self, prefix: &str, base: &str) -> &mut Self {
         self.target.add_rule(prefix, base);
@@ -336,117 +1657,1497 @@ impl Processor {
     }
 
     /// Process a value, using the configuration in this struct.
+    ///
+    /// Any value is accepted. Scalars and `null` outside of objects are returned unchanged.
     pub fn process_value(&self, value: &Value) -> Value {
-        self.process_value_inner(value, &self.context)
+        let mut state = State::new(false);
+        let value = self.process_value_inner(value, &self.context, &mut state)
+            .expect("lenient processing failed");
+        self.finish(value, output_context(&mut state))
+    }
+
+    /// Process a document, like `process_value`, but return `JsonNsError::NotADocument` if the
+    /// root is not an object or array.
+    pub fn process_document(&self, value: &Value) -> Result<Value, JsonNsError> {
+        match *value {
+            Value::Object(_) | Value::Array(_) => Ok(self.process_value(value)),
+            _ => Err(JsonNsError::NotADocument),
+        }
+    }
+
+    /// Process a value, like `process_value`, but resolve relative `@id` values against the given
+    /// base IRI, instead of the `@base` of the context.
+    ///
+    /// This is useful when the location of a document determines its base IRI.
+    pub fn process_value_with_base(&self, value: &Value, base: &str) -> Value {
+        let mut state = State::new(false);
+        state.base = Some(base.to_owned());
+        let value = self.process_value_inner(value, &self.context, &mut state)
+            .expect("lenient processing failed");
+        self.finish(value, output_context(&mut state))
+    }
+
+    /// Process a value, like `process_value`, and call `visitor` with the output name and value
+    /// of every property as it is added to the output, at every level of the document.
+    ///
+    /// Keywords such as `@id` and `@type` are not properties, and are not visited.
+    pub fn process_value_visit<F>(&self, value: &Value, visitor: &mut F) -> Value
+        where F: FnMut(&str, &Value)
+    {
+        let mut state = State::new(false);
+        state.visitor = Some(Visitor(visitor));
+        let value = self.process_value_inner(value, &self.context, &mut state)
+            .expect("lenient processing failed");
+        self.finish(value, output_context(&mut state))
     }
 
     /// Process an object, using the configuration in this struct.
     pub fn process_object(&self, object: &Map) -> Map {
-        self.process_object_inner(object, &self.context)
+        let mut state = State::new(false);
+        let mut object = self.process_object_inner(object, &self.context, &mut state)
+            .expect("lenient processing failed");
+        if let Some(context) = output_context(&mut state) {
+            object.insert("@context".to_owned(), context);
+        }
+        if self.sort_keys {
+            object = sort_keys(object);
+        }
+        object
+    }
+
+    /// Process an object, like `process_object`, but return an error where `process_object` would
+    /// silently recover, like `process_value_strict`.
+    pub fn process_object_strict(&self, object: &Map) -> Result<Map, JsonNsError> {
+        let mut state = State::new(true);
+        let mut object = self.process_object_inner(object, &self.context, &mut state)?;
+        if !state.dropped.is_empty() {
+            return Err(JsonNsError::Dropped(state.dropped));
+        }
+        if let Some(context) = output_context(&mut state) {
+            object.insert("@context".to_owned(), context);
+        }
+        if self.sort_keys {
+            object = sort_keys(object);
+        }
+        Ok(object)
+    }
+
+    /// Process a value that is a single node, and iterate over the properties of the result.
+    ///
+    /// Nested values are left as-is. If the value is not an object, there are no properties.
+    pub fn pairs(&self, value: &Value) -> impl Iterator<Item = (String, Value)> {
+        let object = match *value {
+            Value::Object(ref object) => self.process_object(object),
+            _ => Map::new(),
+        };
+        object.into_iter()
+    }
+
+    /// Start a session, for processing many documents with the same configuration.
+    pub fn session(&self) -> ProcessorSession<'_> {
+        ProcessorSession { processor: self, state: State::new(false) }
+    }
+
+    /// Process a list of independent documents.
+    ///
+    /// Each document is processed starting from the external context in this struct, so an inline
+    /// context in one document never affects another. This is also true for the elements of an
+    /// array passed to `process_value`, but this method makes the intent explicit.
+    pub fn process_documents(&self, documents: &[Value]) -> Vec<Value> {
+        documents.iter()
+            .map(|document| self.process_value(document))
+            .collect()
+    }
+
+    /// Process a value, and flatten the result.
+    ///
+    /// Every node in the document is collected in a top-level `@graph` array, in document order.
+    /// Where a node was nested, only a reference to it remains, in the form of an object with just
+    /// an `@id`. Nodes without an `@id` are given a blank node identifier, such as `_:b0`, which
+    /// are numbered in document order, so the output for a given input is always the same.
+    ///
+    /// Nodes with the same `@id` are merged into one, at the position of the first. Where both
+    /// have the same property, the values are combined into an array, without duplicates.
+    pub fn flatten(&self, value: &Value) -> Value {
+        let mut state = State::new(false);
+        let nodes = self.flatten_nodes(value, &mut state);
+
+        let mut result = Map::with_capacity(1);
+        let nodes = nodes.into_iter().map(Value::Object).collect();
+        result.insert("@graph".to_owned(), Value::Array(nodes));
+        self.finish(Value::Object(result), output_context(&mut state))
+    }
+
+    /// Process a value, and collect the flattened nodes with their blank node identifiers.
+    fn flatten_nodes(&self, value: &Value, state: &mut State) -> Vec<Map> {
+        state.flatten = true;
+        self.process_value_inner(value, &self.context, state)
+            .expect("lenient processing failed");
+
+        let mut nodes = merge_nodes(mem::take(&mut state.nodes));
+        self.label_blank_nodes(&mut nodes);
+        if self.strip_blank_nodes {
+            strip_blank_nodes(&mut nodes);
+        }
+        nodes
+    }
+
+    /// Check whether the names in the output for a value can be expanded back to the same absolute
+    /// IRIs. This is a debugging aid, to find rules in the target context that produce names which
+    /// don't expand, for example because the remainder contains a colon.
+    ///
+    /// The value is processed with and without the target context, and the former output is then
+    /// processed again with a context equivalent to the target context. The result must be equal
+    /// to the output without target context. Only names are checked, so `wrap_strings`,
+    /// `value_transform`, `type_aliases` and `order_key` are disabled for the check.
+    pub fn roundtrip_check(&self, value: &Value) -> bool {
+        let processor = Processor {
+            wrap_strings: false,
+            value_transform: None,
+            type_aliases: false,
+            order_key: None,
+            ..self.clone()
+        };
+        let output = processor.process_value(value);
+        let expected = Processor {
+            target: TargetContext::default(),
+            auto_prefix: None,
+            rename: BTreeMap::new(),
+            ..processor
+        }.process_value(value);
+
+        let mut context = Context::new();
+        for (prefix, base) in self.target.rules.iter().rev() {
+            if prefix.is_empty() {
+                context.ns = Some(base.clone());
+            } else {
+                context.prefixes.insert(prefix.clone(), base.clone());
+            }
+        }
+        Processor::with_context(context).process_value(&output) == expected
+    }
+
+    /// Process a value, using the configuration in this struct, but return an error where
+    /// `process_value` would silently recover.
+    ///
+    /// Properties that can't be resolved are collected, and returned together in a single
+    /// `JsonNsError::Dropped` error. Other errors abort processing immediately.
+    pub fn process_value_strict(&self, value: &Value) -> Result<Value, JsonNsError> {
+        let mut state = State::new(true);
+        let value = self.process_value_inner(value, &self.context, &mut state)?;
+        if state.dropped.is_empty() {
+            Ok(self.finish(value, output_context(&mut state)))
+        } else {
+            Err(JsonNsError::Dropped(state.dropped))
+        }
+    }
+
+    /// Apply output settings to the result of a processing run.
+    fn finish(&self, value: Value, context: Option<Value>) -> Value {
+        let value = add_context(value, context);
+        if self.sort_keys {
+            sort_value(value)
+        } else {
+            value
+        }
     }
 
     /// Process a value with a local context.
-    fn process_value_inner(&self, value: &Value, context: &Context) -> Value {
-        match *value {
+    fn process_value_inner(&self, value: &Value, context: &Context, state: &mut State)
+        -> Result<Value, JsonNsError>
+    {
+        // Guard against deep nesting, which would otherwise exhaust the stack. In lenient mode, a
+        // value that is too deep is replaced with an empty one.
+        let nested = value.is_array() || value.is_object();
+        if nested && self.max_depth.is_some_and(|max| state.depth >= max) {
+            if state.strict {
+                return Err(JsonNsError::LimitExceeded(Limit::Depth));
+            }
+            state.output_nodes += 1;
+            return Ok(match *value {
+                Value::Array(_) => Value::Array(Vec::new()),
+                _ => Value::Object(Map::new()),
+            });
+        }
+
+        state.depth += 1;
+        let value = self.process_value_nested(value, context, state);
+        state.depth -= 1;
+        let value = value?;
+        self.check_output_nodes(state)?;
+        Ok(value)
+    }
+
+    /// Process a value with a local context, once it is accounted for in the nesting depth.
+    ///
+    /// Every value in the result is counted in `State::output_nodes`.
+    fn process_value_nested(&self, value: &Value, context: &Context, state: &mut State)
+        -> Result<Value, JsonNsError>
+    {
+        Ok(match *value {
             Value::Array(ref array) => {
-                let array = array.iter()
-                    .map(|value| self.process_value_inner(value, context))
-                    .collect::<Vec<_>>();
-                Value::Array(array)
+                let depth = state.path.len();
+                let mut result = Vec::with_capacity(array.len());
+                for (index, value) in array.iter().enumerate() {
+                    state.path.truncate(depth);
+                    state.enter(index);
+                    let value = self.process_value_inner(value, context, state)?;
+                    if !self.is_stripped(&value, state) {
+                        result.push(value);
+                    }
+                }
+                state.path.truncate(depth);
+                state.output_nodes += 1;
+                Value::Array(result)
+            },
+            Value::Object(ref object) if object.contains_key("@value") => {
+                let value = Value::Object(self.process_value_object(object, context, state));
+                state.output_nodes += value_size(&value);
+                value
             },
             Value::Object(ref object) => {
-                Value::Object(self.process_object_inner(object, context))
+                let slot = state.reserve_node();
+                let node = self.process_object_inner(object, context, state)?;
+                state.output_nodes += 1;
+                Value::Object(state.add_node(slot, node))
+            },
+            ref value => {
+                let value = self.leaf_value(value);
+                let value = match (&self.value_transform, &state.property) {
+                    (Some(transform), Some(property)) => (transform.0)(property, &value),
+                    _ => value,
+                };
+                // In expanded form, property values are value objects, with the default language
+                // and direction for strings.
+                let value = if state.expand && state.property.is_some() && !value.is_null() {
+                    expand_scalar(value, context, state.direction.as_deref())
+                } else {
+                    value
+                };
+                state.output_nodes += value_size(&value);
+                value
             },
-            ref value => value.clone(),
+        })
+    }
+
+    /// Process a value object, which is a literal rather than a node.
+    fn process_value_object(&self, object: &Map, context: &Context, state: &mut State) -> Map {
+        let mut result = Map::with_capacity(object.len());
+        for (key, value) in object {
+            match key.as_str() {
+                "@type" => {
+                    // The datatype, a single name which expands to an absolute IRI.
+                    let iri = value.as_str().and_then(|string| context.expand_type(string));
+                    if let Some(iri) = iri {
+                        result.insert(key.clone(), Value::String(self.compact_iri(&iri, state)));
+                    }
+                },
+                "@value" => {
+                    result.insert(key.clone(), self.leaf_value(value));
+                },
+                key if VALUE_KEYWORDS.contains(&key) => {
+                    // Other keywords are copied as-is.
+                    result.insert(key.to_owned(), value.clone());
+                },
+                _ => {
+                    // Anything else is not allowed in a value object, and dropped.
+                },
+            }
         }
+        result
     }
 
     /// Process an object with a local context.
-    fn process_object_inner(&self, object: &Map, context: &Context) -> Map {
+    ///
+    /// The values of properties in the result are counted in `State::output_nodes`, but not the
+    /// object itself.
+    fn process_object_inner(&self, object: &Map, context: &Context, state: &mut State)
+        -> Result<Map, JsonNsError>
+    {
         // Extend the active context with the local context, if present.
-        let local_context = object.get("@context").map(|value| {
-            let mut context = context.clone();
-            context.merge_value(value);
-            context
-        });
+        let local_context = match object.get("@context") {
+            Some(value) => Some(self.extend_context(context, value, state)?),
+            None => None,
+        };
         let context = local_context.as_ref().unwrap_or(context);
 
+        // Nested nodes return to the context before a non-propagating context, if any.
+        let base = context.previous.as_deref().unwrap_or(context);
+
+        let depth = state.path.len();
         let mut result = Map::with_capacity(object.len());
+        let mut nests = Vec::new();
+        let mut order = state.orders.pop().unwrap_or_default();
         for (key, value) in object {
-            if key.starts_with('@') {
-                // A keyword property.
-                match key.as_str() {
-                    "@id" => {
-                        // Document ID, must be an absolute IRI.
-                        if let Some(iri) = value.as_str().filter(|s| is_absolute_iri(s)) {
-                            result.insert(key.clone(), Value::String(iri.to_owned()));
-                        }
-                    },
-                    "@type" => {
-                        // Document type, a string or array of strings, each of which expands to an
-                        // absolute IRI. (We don't support `@type` on values, like JSON-LD.)
-                        let value = OneOrMany::from(value)
-                            .filter_map(|value| value.as_str())
-                            .filter_map(|string| context.expand_name(string))
-                            .map(|iri| self.target.compact_iri(&iri).into_owned())
-                            .map(Value::String)
-                            .collect::<Vec<_>>();
-                        if !value.is_empty() {
-                            result.insert(key.clone(), Value::Array(value));
-                        }
-                    },
-                    _ => {
-                        // Ignore `@context` (already processed) and other unrecognized keywords.
-                    },
-                }
-
-                continue;
+            state.path.truncate(depth);
+            state.enter(key);
+            if self.order_key.is_some() {
+                record_order(&mut order, &result);
             }
 
-            // Look for an alias.
-            let resolved = context.aliases.get(key).map(String::as_str).unwrap_or(key);
+            // Count the property against the budgets.
+            if !self.count_property(state)? || !self.check_output_nodes(state)? {
+                break;
+            }
 
-            // Resolve in the current context.
-            let resolved = match context.expand_name(resolved) {
-                Some(iri) => self.target.compact_iri(&iri).into_owned(),
-                None => continue,
-            };
+            // Resolve aliases and expand the name in the current context.
+            let (resolved, iri) = match context.resolve_name(key) {
+                Name::Keyword(keyword) => {
+                    // A keyword property, possibly through an alias.
+                    match keyword {
+                        "@id" => {
+                            // Document ID, which expands to an absolute IRI. The base IRI given
+                            // to the processor overrides the one in the context. It is compacted
+                            // like `@type` and property names.
+                            let base = state.base.as_deref().or(context.base());
+                            let iri = value.as_str().and_then(|id| context.expand_id(id, base));
+                            if let Some(iri) = iri {
+                                let iri = self.compact_iri(&iri, state);
+                                state.output_nodes += 1;
+                                state.insert_output(&mut result, keyword.to_owned(), Value::String(iri));
+                            }
+                        },
+                        "@type" => {
+                            // Document type, a string or array of strings, each of which expands
+                            // to an absolute IRI. (The datatype of a value object is handled
+                            // separately.)
+                            let mut types = mem::take(&mut state.types);
+                            types.extend(OneOrMany::from(value)
+                                .filter_map(|value| value.as_str())
+                                .filter_map(|string| context.expand_type(string))
+                                .map(|iri| Value::String(self.compact_type(&iri, context, state))));
+                            if !types.is_empty() {
+                                state.output_nodes += 1 + types.len();
+                                let mut array = Vec::with_capacity(types.len());
+                                array.append(&mut types);
+                                state.insert_output(&mut result, keyword.to_owned(), Value::Array(array));
+                            }
+                            state.types = types;
+                        },
+                        "@graph" | "@included" => {
+                            // The nodes of a (named) graph, or additional node objects, which are
+                            // not the value of any property. Either way, they are processed like
+                            // nested nodes.
+                            let depth = state.path.len();
+                            let mut array = Vec::new();
+                            for (index, node) in OneOrMany::from(value).enumerate() {
+                                if let Value::Object(ref node) = *node {
+                                    state.path.truncate(depth);
+                                    if value.is_array() {
+                                        state.enter(index);
+                                    }
 
-            // Look for a container mapping of the original property name.
-            result.insert(resolved, match context.container.get(key).map(String::as_str) {
-                Some("@language") => {
-                    // An internationalised property.
-                    match *value {
-                        Value::String(_) => {
-                            // Normalise a string value to a language map with a single entry for
-                            // the context default language.
-                            let mut object = Map::with_capacity(1);
-                            object.insert(context.lang.clone(), value.clone());
-                            Value::Object(object)
+                                    let slot = state.reserve_node();
+                                    let node = self.process_object_inner(node, base, state)?;
+                                    state.output_nodes += 1;
+                                    let node = Value::Object(state.add_node(slot, node));
+                                    if !self.is_stripped(&node, state) {
+                                        array.push(node);
+                                    }
+                                }
+                            }
+                            if !array.is_empty() {
+                                state.output_nodes += 1;
+                                state.insert_output(&mut result, keyword.to_owned(), Value::Array(array));
+                            }
                         },
-                        Value::Object(ref object) => {
-                            // Filter non-string values from the object.
-                            let object = object.iter()
-                                .filter(|(_, value)| value.is_string())
-                                .map(|(key, value)| (key.clone(), value.clone()))
-                                .collect();
-                            Value::Object(object)
+                        "@nest" => {
+                            // Property groups are lifted into this object once its own properties
+                            // have been processed, so those are merged rather than replaced.
+                            if state.strict && self.require_version && context.version.is_none() {
+                                return Err(JsonNsError::VersionRequired(keyword.to_owned()));
+                            }
+                            nests.push((key, value));
                         },
                         _ => {
-                            // Drop unrecognised values.
-                            continue;
+                            // Ignore `@context` (already processed) and other unrecognized
+                            // keywords.
                         },
                     }
+                    continue;
                 },
-                _ => {
-                    // No or unrecognized container mapping, which we treat as a normal value.
-                    // Expand it by recursing.
-                    self.process_value_inner(value, context)
+                Name::Iri(ref iri) if !self.is_allowed(iri) => continue,
+                Name::Iri(iri) => (self.property_name(&iri, state), iri),
+                Name::Drop => {
+                    if state.strict {
+                        let path = state.pointer();
+                        state.dropped.push(Dropped { property: key.clone(), path });
+                    }
+                    continue;
                 },
-            });
+            };
+
+            // A JSON literal is copied as-is, without processing.
+            if context.types.get(key).map(String::as_str) == Some("@json") {
+                let value = if state.expand {
+                    let mut object = Map::with_capacity(2);
+                    object.insert("@value".to_owned(), value.clone());
+                    object.insert("@type".to_owned(), Value::String("@json".to_owned()));
+                    Value::Array(vec![Value::Object(object)])
+                } else {
+                    value.clone()
+                };
+                state.output_nodes += value_size(&value);
+                state.visit(&resolved, &value);
+                state.insert_output(&mut result, resolved, value);
+                continue;
+            }
+
+            // Look for a container mapping of the original property name, or the absolute IRI.
+            let container = context.container_for(key, &iri);
+            let set = context.is_set(key, &iri);
+
+            // String values of a property with `@type: @id` are node references, also inside an
+            // index map. They are processed as such, and turned back into strings in compacted
+            // output, where the coercion is recorded for the output context.
+            let coerced = context.types.get(key).map(String::as_str) == Some("@id");
+            let coerced_value;
+            let value = if coerced {
+                let recorded = state.coercions.iter().any(|(name, _)| *name == resolved);
+                if !state.expand && self.auto_prefix.is_some() && !recorded {
+                    state.coercions.push((resolved.clone(), iri.to_string()));
+                }
+                coerced_value = match *value {
+                    Value::Object(ref object) if container == Some("@index") => {
+                        Value::Object(object.iter()
+                            .map(|(index, value)| (index.clone(), wrap_ids(value)))
+                            .collect())
+                    },
+                    ref value => wrap_ids(value),
+                };
+                &coerced_value
+            } else {
+                value
+            };
+
+            // Extend the active context with the scoped context of the property, if present. This
+            // applies only while processing the value.
+            let scoped_context = match context.scoped.get(key) {
+                Some(value) => Some(self.extend_context(base, value, state)?),
+                None => None,
+            };
+            let (context, node_context) = match scoped_context {
+                Some(ref context) => (context, context),
+                None => (context, base),
+            };
+
+            // The base direction of strings in expanded form, which the term may override.
+            let direction = context.direction_of(key);
+
+            let value = match container {
+                Some("@language") => {
+                    // An internationalised property.
+                    match *value {
+                        Value::String(_) => {
+                            // Normalise a string value to a language map with a single entry for
+                            // the context default language.
+                            let value = if set {
+                                Value::Array(vec![self.leaf_value(value)])
+                            } else {
+                                self.leaf_value(value)
+                            };
+                            let mut object = Map::with_capacity(1);
+                            let lang = self.language_key(context, "");
+                            object.insert(lang.to_owned(), value);
+                            Value::Object(object)
+                        },
+                        Value::Object(_) | Value::Array(_) => {
+                            // Filter non-string values from the object. An array of objects is
+                            // merged into one, with later entries overriding earlier ones, and
+                            // dropped if nothing remains.
+                            let object: Map = OneOrMany::from(value)
+                                .filter_map(Value::as_object)
+                                .flat_map(|object| object.iter())
+                                .filter_map(|(key, value)| {
+                                    let value = self.language_value(value, set)?;
+                                    Some((key.clone(), value))
+                                })
+                                .collect();
+                            if object.is_empty() && value.is_array() {
+                                continue;
+                            }
+                            Value::Object(object)
+                        },
+                        _ => {
+                            // Drop unrecognised values.
+                            continue;
+                        },
+                    }
+                },
+                Some("@type") => {
+                    // A type map, keyed by the type of each node.
+                    let object = match *value {
+                        Value::Object(ref object) => object,
+                        _ => {
+                            // Drop unrecognised values.
+                            continue;
+                        },
+                    };
+
+                    // Flatten the map into an array of nodes, adding the key to the `@type` of
+                    // each. Keys that don't expand drop only their own entry.
+                    let depth = state.path.len();
+                    let mut array = Vec::with_capacity(object.len());
+                    for (name, value) in object {
+                        let ty = match context.expand_type(name) {
+                            Some(iri) => Value::String(self.compact_type(&iri, context, state)),
+                            None => continue,
+                        };
+                        for (index, node) in OneOrMany::from(value).enumerate() {
+                            if let Value::Object(ref node) = *node {
+                                state.path.truncate(depth);
+                                state.enter(name);
+                                if value.is_array() {
+                                    state.enter(index);
+                                }
+
+                                let slot = state.reserve_node();
+                                let mut node = self.process_object_inner(node, node_context, state)?;
+                                state.output_nodes += 1 + add_type(&mut node, ty.clone());
+                                let node = Value::Object(state.add_node(slot, node));
+                                if !self.is_stripped(&node, state) {
+                                    array.push(node);
+                                }
+                            }
+                        }
+                    }
+                    state.output_nodes += 1;
+                    Value::Array(array)
+                },
+                Some("@index") => {
+                    // An index map, keyed by an arbitrary index of each value.
+                    let object = match *value {
+                        Value::Object(ref object) => object,
+                        _ => {
+                            // Drop unrecognised values.
+                            continue;
+                        },
+                    };
+
+                    // Flatten the map into an array of values. The keys are dropped, unless
+                    // `index_key` is set.
+                    let depth = state.path.len();
+                    let mut array = Vec::with_capacity(object.len());
+                    for (index, value) in object {
+                        state.path.truncate(depth);
+                        state.enter(index);
+                        let value = self.process_property_value(
+                            value, iri.to_string(), direction, node_context, state);
+                        let values = match value? {
+                            Value::Array(array) => {
+                                state.uncount(1);
+                                array
+                            },
+                            value => vec![value],
+                        };
+                        for value in values {
+                            if value.is_null() {
+                                state.uncount(1);
+                            } else if !self.is_stripped(&value, state) {
+                                array.push(self.add_index(value, index, state));
+                            }
+                        }
+                    }
+                    state.path.truncate(depth);
+                    state.output_nodes += 1;
+                    Value::Array(array)
+                },
+                _ => {
+                    // No or unrecognized container mapping, which we treat as a normal value.
+                    // Expand it by recursing.
+                    let value = self.process_property_value(
+                        value, iri.into_owned(), direction, node_context, state)?;
+                    if self.is_stripped(&value, state) {
+                        continue;
+                    }
+                    value
+                },
+            };
+
+            // Wrap a plain string in a language map, if enabled. In expanded form, property values
+            // are always arrays, and language maps are replaced with value objects.
+            let value = match value {
+                Value::String(string) if self.wrap_strings => {
+                    let lang = self.language_key(context, "@none");
+                    let mut map = Map::with_capacity(1);
+                    map.insert(lang.to_owned(), Value::String(string));
+                    let value = if state.expand {
+                        expand_language_map(map, direction)
+                    } else {
+                        Value::Object(map)
+                    };
+                    // The string itself was already counted.
+                    state.output_nodes += value_size(&value) - 1;
+                    value
+                },
+                Value::Object(map) if state.expand && container == Some("@language") => {
+                    expand_language_map(map, direction)
+                },
+                Value::Array(array) => Value::Array(array),
+                value if state.expand || (set && container != Some("@language")) => {
+                    state.output_nodes += 1;
+                    Value::Array(vec![value])
+                },
+                value => value,
+            };
+            if container == Some("@language") {
+                // Language maps are built here, so they are counted only now.
+                state.output_nodes += value_size(&value);
+            }
+            // Flattened references still carry temporary ids at this point, which
+            // are only relabeled as `@id` keys, so they are kept as node objects.
+            let value = if coerced && !state.expand && !state.flatten {
+                let mut removed = 0;
+                let value = unwrap_ids(value, &mut removed);
+                state.uncount(removed);
+                value
+            } else {
+                value
+            };
+
+            state.visit(&resolved, &value);
+            state.insert_output(&mut result, resolved, value);
+        }
+        for (key, value) in nests {
+            state.path.truncate(depth);
+            state.enter(key);
+            self.process_nest(value, context, state, &mut result)?;
         }
+        state.path.truncate(depth);
 
-        result
+        if let Some(ref order_key) = self.order_key {
+            record_order(&mut order, &result);
+            let value: Vec<_> = order.drain(..).map(Value::String).collect();
+            state.output_nodes += 1 + value.len();
+            state.insert_output(&mut result, order_key.clone(), Value::Array(value));
+        }
+        state.orders.push(order);
+
+        Ok(result)
+    }
+
+    /// Process the value of a property, which is the current property for its leaf values.
+    fn process_property_value(&self, value: &Value, iri: String, direction: Option<&str>,
+        context: &Context, state: &mut State) -> Result<Value, JsonNsError>
+    {
+        let previous = state.property.replace(iri);
+        let previous_direction = mem::replace(&mut state.direction, direction.map(str::to_owned));
+        let value = self.process_value_inner(value, context, state);
+        state.property = previous;
+        state.direction = previous_direction;
+        value
+    }
+
+    /// Process the value of an `@nest` property, a group of properties without semantics of its
+    /// own. The properties are lifted into the enclosing object, and may themselves contain
+    /// `@nest`. Anything other than an object is dropped.
+    fn process_nest(&self, value: &Value, context: &Context, state: &mut State, result: &mut Map)
+        -> Result<(), JsonNsError>
+    {
+        let depth = state.path.len();
+        for (index, nested) in OneOrMany::from(value).enumerate() {
+            if let Value::Object(ref nested) = *nested {
+                state.path.truncate(depth);
+                if value.is_array() {
+                    state.enter(index);
+                }
+
+                let nested = self.process_object_inner(nested, context, state)?;
+                for (key, value) in nested {
+                    if is_keyword(&key) {
+                        state.uncount(value_size(&value));
+                        continue;
+                    }
+                    match result.get_mut(&key) {
+                        Some(existing) => {
+                            state.uncount(value_size(existing) + value_size(&value));
+                            merge_values(existing, value);
+                            state.output_nodes += value_size(existing);
+                        },
+                        None => {
+                            result.insert(key, value);
+                        },
+                    }
+                }
+            }
+        }
+        state.path.truncate(depth);
+        Ok(())
+    }
+
+    /// Compact the absolute IRI of a property, and apply `lowercase` and `rename`.
+    fn property_name(&self, iri: &str, state: &mut State) -> String {
+        let mut name = self.compact_iri(iri, state);
+        if name != iri {
+            match self.lowercase {
+                Some(Lowercase::Local) => {
+                    let start = name.find(':').map_or(0, |index| index + 1);
+                    name = format!("{}{}", &name[..start], name[start..].to_lowercase());
+                },
+                Some(Lowercase::All) => name = name.to_lowercase(),
+                None => {},
+            }
+        }
+        match self.rename.get(&name) {
+            Some(name) => name.clone(),
+            None => name,
+        }
+    }
+
+    /// The language map key for a string without a language, using `fallback` if neither the
+    /// active context nor `untagged_key` defines one.
+    fn language_key<'c>(&'c self, context: &'c Context, fallback: &'c str) -> &'c str {
+        match context.language() {
+            Some(lang) => lang,
+            None => self.untagged_key.as_deref().unwrap_or(fallback),
+        }
+    }
+
+    /// Whether a property is allowed in the output, according to `allowed_properties` and
+    /// `denied_properties`.
+    fn is_allowed(&self, iri: &str) -> bool {
+        if self.denied_properties.contains(iri) {
+            return false;
+        }
+        match self.allowed_properties {
+            Some(ref allowed) => allowed.contains(iri),
+            None => true,
+        }
+    }
+
+    /// Whether a processed value is a nested node that should be removed, according to
+    /// `strip_blank_nodes`. When flattening, nodes are instead removed afterwards.
+    ///
+    /// A value that should be removed is no longer counted in `State::output_nodes`.
+    fn is_stripped(&self, value: &Value, state: &mut State) -> bool {
+        if !self.strip_blank_nodes || state.flatten {
+            return false;
+        }
+        let stripped = match *value {
+            Value::Object(ref object) if !object.contains_key("@value") => {
+                object.get("@id").and_then(Value::as_str).is_none_or(is_blank_node)
+            },
+            _ => false,
+        };
+        if stripped {
+            state.uncount(value_size(value));
+        }
+        stripped
+    }
+
+    /// Filter a value in a language map, returning `None` if it should be dropped.
+    ///
+    /// For a `@set` container, arrays are always allowed, and strings are wrapped in an array.
+    fn language_value(&self, value: &Value, set: bool) -> Option<Value> {
+        match *value {
+            Value::String(_) if set => Some(Value::Array(vec![self.leaf_value(value)])),
+            Value::String(_) => Some(self.leaf_value(value)),
+            Value::Array(ref array) if self.language_arrays || set => {
+                let array = array.iter()
+                    .filter(|value| value.is_string())
+                    .map(|value| self.leaf_value(value))
+                    .collect::<Vec<_>>();
+                if array.is_empty() {
+                    None
+                } else {
+                    Some(Value::Array(array))
+                }
+            },
+            _ => None,
+        }
+    }
+
+    /// Record the key of an index map in a value, if `index_key` is set.
+    fn add_index(&self, value: Value, index: &str, state: &mut State) -> Value {
+        let key = match self.index_key {
+            Some(ref key) if index != "@none" => key,
+            _ => return value,
+        };
+        let mut object = match value {
+            Value::Object(object) => object,
+            value => {
+                let mut object = Map::with_capacity(2);
+                object.insert("@value".to_owned(), value);
+                state.output_nodes += 1;
+                object
+            },
+        };
+        object.insert(key.clone(), Value::String(index.to_owned()));
+        state.output_nodes += 1;
+        Value::Object(object)
+    }
+
+    /// Copy a literal value, trimming it if it's a string and `trim_strings` is set.
+    fn leaf_value(&self, value: &Value) -> Value {
+        match *value {
+            Value::String(ref string) if self.trim_strings => Value::String(string.trim().to_owned()),
+            _ => value.clone(),
+        }
+    }
+
+    /// Create a new context by merging an `@context` value into the active context.
+    fn extend_context(&self, context: &Context, value: &Value, state: &mut State)
+        -> Result<Context, JsonNsError>
+    {
+        let mut resolver = Resolver {
+            loader: self.loader.as_deref(),
+            strict: state.strict,
+            loaded: state.contexts,
+            max_loaded: self.max_contexts,
+            require_version: self.require_version,
+            ..Resolver::default()
+        };
+        let mut context = context.clone();
+        let result = context.merge_value_inner(value, &mut resolver);
+        state.contexts = resolver.loaded;
+        result?;
+        Ok(context)
+    }
+
+    /// Assign blank node identifiers to flattened nodes.
+    ///
+    /// Labels are generated in document order, for nodes with a temporary identifier and, if
+    /// `relabel_blank_nodes` is set, for nodes with a blank node identifier from the input.
+    fn label_blank_nodes(&self, nodes: &mut [Map]) {
+        // Labels from the input that are kept must not be generated.
+        let relabel = |id: &str| {
+            id.starts_with('@') || (self.relabel_blank_nodes && id.starts_with("_:"))
+        };
+        let taken = nodes.iter()
+            .filter_map(|node| node.get("@id").and_then(Value::as_str))
+            .filter(|id| id.starts_with("_:") && !relabel(id))
+            .map(str::to_owned)
+            .collect::<HashSet<_>>();
+
+        let mut labels = HashMap::new();
+        let mut counter = 0;
+        for node in nodes.iter() {
+            let id = match node.get("@id").and_then(Value::as_str) {
+                Some(id) if relabel(id) && !labels.contains_key(id) => id,
+                _ => continue,
+            };
+            let label = loop {
+                let label = format!("_:b{}", counter);
+                counter += 1;
+                if !taken.contains(&label) {
+                    break label;
+                }
+            };
+            labels.insert(id.to_owned(), label);
+        }
+
+        for node in nodes {
+            relabel_ids(node, &labels);
+        }
+    }
+
+    /// Compact an absolute IRI using the target context, memoizing the result if enabled.
+    fn compact_iri(&self, iri: &str, state: &mut State) -> String {
+        if !self.memoize_names {
+            return self.compact_iri_uncached(iri, state);
+        }
+
+        if let Some(name) = state.names.get(iri) {
+            return name.clone();
+        }
+        let name = self.compact_iri_uncached(iri, state);
+        state.names.insert(iri.to_owned(), name.clone());
+        name
+    }
+
+    /// Compact a node type, using an alias in the active context if enabled.
+    fn compact_type(&self, iri: &str, context: &Context, state: &mut State) -> String {
+        if self.type_aliases {
+            let alias = context.aliases.iter()
+                .find(|&(_, alias)| context.expand_name(alias).is_some_and(|other| other == iri))
+                .map(|(term, _)| term.clone());
+            if let Some(alias) = alias {
+                return alias;
+            }
+        }
+        self.compact_iri(iri, state)
+    }
+
+    /// Compact an absolute IRI using the target context, generating a prefix if enabled.
+    fn compact_iri_uncached(&self, iri: &str, state: &mut State) -> String {
+        let name = self.target.compact_iri(iri);
+        let auto = match self.auto_prefix {
+            Some(ref auto) if name == iri && !is_blank_node(iri) => auto,
+            _ => return name.into_owned(),
+        };
+
+        // Split after the last delimiter, but only if there's a non-empty suffix.
+        let split = iri.rfind(|c| auto.delimiters.contains(&c))
+            .map(|index| index + 1)
+            .filter(|&index| index < iri.len());
+        let (base, suffix) = match split {
+            Some(index) => iri.split_at(index),
+            None => return name.into_owned(),
+        };
+
+        // Find a previously generated prefix, or generate a new one that doesn't clash with the
+        // target context.
+        let existing = state.prefixes.iter()
+            .find(|&(_, existing)| existing == base)
+            .map(|(prefix, _)| prefix.clone());
+        let prefix = match existing {
+            Some(prefix) => prefix,
+            None => {
+                let prefix = (0..)
+                    .map(|number| format!("{}{}", auto.name, number))
+                    .find(|prefix| {
+                        self.target.rules.iter().chain(&state.prefixes)
+                            .all(|(other, _)| other != prefix)
+                    })
+                    .expect("no prefix name available");
+                state.prefixes.push((prefix.clone(), base.to_owned()));
+                prefix
+            },
+        };
+        format!("{}:{}", prefix, suffix)
+    }
+
+    /// Count a property against the `max_properties` budget.
+    ///
+    /// Returns `false` if the budget is exhausted and the property should be skipped.
+    fn count_property(&self, state: &mut State) -> Result<bool, JsonNsError> {
+        state.properties += 1;
+        match self.max_properties {
+            Some(max) if state.properties > max => {
+                if state.strict {
+                    Err(JsonNsError::LimitExceeded(Limit::Properties))
+                } else {
+                    Ok(false)
+                }
+            },
+            _ => Ok(true),
+        }
+    }
+
+    /// Check the `max_output_nodes` budget.
+    ///
+    /// Returns `false` if the budget is exhausted and no more properties should be added.
+    fn check_output_nodes(&self, state: &State) -> Result<bool, JsonNsError> {
+        match self.max_output_nodes {
+            Some(max) if state.output_nodes > max => {
+                if state.strict {
+                    Err(JsonNsError::LimitExceeded(Limit::OutputNodes))
+                } else {
+                    Ok(false)
+                }
+            },
+            _ => Ok(true),
+        }
+    }
+}
+
+/// A session for processing many documents, created with `Processor::session`.
+///
+/// Processing with a session gives the same results as with the `Processor` itself. If
+/// `Processor::memoize_names` is set, memoized compacted names are kept between calls, unless
+/// `Processor::auto_prefix` is set, because generated prefixes differ between documents. The
+/// lists of generated prefixes and coercions, the buffer that node types are collected in, and the
+/// buffers that record key order for `Processor::order_key` also keep their capacity. The output
+/// itself is still allocated for each document.
+#[derive(Debug)]
+pub struct ProcessorSession<'a> {
+    processor: &'a Processor,
+    state: State<'static>,
+}
+
+impl<'a> ProcessorSession<'a> {
+    /// Process a value, like `Processor::process_value`.
+    pub fn process_value(&mut self, value: &Value) -> Value {
+        let processor = self.processor;
+        self.state.reset(processor.auto_prefix.is_none());
+        let value = processor.process_value_inner(value, &processor.context, &mut self.state)
+            .expect("lenient processing failed");
+        processor.finish(value, output_context(&mut self.state))
+    }
+}
+
+/// Keywords recognized in a term definition.
+const TERM_KEYWORDS: &[&str] = &[
+    "@container", "@context", "@direction", "@id", "@prefix", "@protected", "@type",
+];
+
+/// Container mappings recognized in a term definition.
+const CONTAINERS: &[&str] = &["@index", "@language", "@type"];
+
+/// Whether a container mapping is a recognized container, or an array combining `@set` with at
+/// most one recognized container.
+fn is_valid_container(value: &Value) -> bool {
+    match *value {
+        Value::String(ref container) => container == "@set" || CONTAINERS.contains(&&**container),
+        Value::Array(ref array) => {
+            let mut others = array.iter().filter(|value| value.as_str() != Some("@set"));
+            others.clone().count() <= 1 && others.all(is_valid_container)
+        },
+        _ => false,
+    }
+}
+
+/// Check an `@context` value, adding warnings with paths relative to the given path.
+fn validate_context(value: &Value, path: &str, warnings: &mut Vec<Warning>) {
+    match *value {
+        Value::Array(ref array) => {
+            for (index, value) in array.iter().enumerate() {
+                validate_context(value, &format!("{}/{}", path, index), warnings);
+            }
+        },
+        Value::Object(ref object) => {
+            for (key, value) in object {
+                let path = format!("{}/{}", path, escape_pointer(key));
+                if is_keyword(key) {
+                    validate_context_keyword(key, value, &path, warnings);
+                } else {
+                    validate_term(key, value, &path, warnings);
+                }
+            }
+        },
+        Value::Null | Value::String(_) => {},
+        _ => warnings.push(Warning::InvalidDefinition(path.to_owned())),
+    }
+}
+
+/// Check a keyword in an `@context` object.
+fn validate_context_keyword(key: &str, value: &Value, path: &str, warnings: &mut Vec<Warning>) {
+    let valid = match key {
+        "@vocab" => {
+            if value.as_str().is_some_and(|s| !is_absolute_iri(s)) {
+                warnings.push(Warning::InvalidIri(path.to_owned()));
+                return;
+            }
+            value.is_string() || value.is_null()
+        },
+        "@language" => value.is_string() || value.is_null(),
+        "@direction" => is_direction_value(value),
+        "@base" => value.is_string() || value.is_null(),
+        "@protected" | "@propagate" => value.is_boolean(),
+        "@version" => is_version(value),
+        "@import" => {
+            if value.as_str().is_some_and(|s| !is_absolute_iri(s)) {
+                warnings.push(Warning::InvalidIri(path.to_owned()));
+                return;
+            }
+            value.is_string()
+        },
+        _ => {
+            warnings.push(Warning::UnknownKeyword(path.to_owned()));
+            return;
+        },
+    };
+    if !valid {
+        warnings.push(Warning::InvalidDefinition(path.to_owned()));
+    }
+}
+
+/// Whether a value is a recognized `@version`.
+fn is_version(value: &Value) -> bool {
+    value.as_f64() == Some(1.1)
+}
+
+/// Check a term definition in an `@context` object.
+fn validate_term(key: &str, value: &Value, path: &str, warnings: &mut Vec<Warning>) {
+    match *value {
+        Value::String(ref string) => {
+            if !is_curie_prefix(key) {
+                warnings.push(Warning::InvalidDefinition(path.to_owned()));
+            } else if !is_absolute_iri(string) {
+                warnings.push(Warning::InvalidIri(path.to_owned()));
+            }
+        },
+        Value::Object(ref object) => {
+            for (key, value) in object {
+                let path = format!("{}/{}", path, escape_pointer(key));
+                match key.as_str() {
+                    "@id" => {
+                        match value.as_str() {
+                            Some("@context") => {
+                                warnings.push(Warning::KeywordAlias(path));
+                            },
+                            Some(_) => {},
+                            None => warnings.push(Warning::InvalidDefinition(path)),
+                        }
+                    },
+                    "@container" if !is_valid_container(value) => {
+                        warnings.push(Warning::UnknownContainer(path));
+                    },
+                    "@context" => {
+                        validate_context(value, &path, warnings);
+                    },
+                    "@prefix" | "@protected" if !value.is_boolean() => {
+                        warnings.push(Warning::InvalidDefinition(path));
+                    },
+                    "@type" if !value.is_string() => {
+                        warnings.push(Warning::InvalidDefinition(path));
+                    },
+                    "@direction" if !is_direction_value(value) => {
+                        warnings.push(Warning::InvalidDefinition(path));
+                    },
+                    key if !TERM_KEYWORDS.contains(&key) => {
+                        warnings.push(Warning::UnknownKeyword(path));
+                    },
+                    _ => {},
+                }
+            }
+        },
+        Value::Null => {},
+        _ => warnings.push(Warning::InvalidDefinition(path.to_owned())),
+    }
+}
+
+/// Escape a JSON pointer segment.
+fn escape_pointer(segment: &str) -> String {
+    segment.replace('~', "~0").replace('/', "~1")
+}
+
+/// Expand a document, independent of a `Processor`.
+///
+/// The result is similar to the expanded form of JSON-LD: all names are absolute IRIs, property
+/// values are always arrays, and strings, numbers, booleans and language maps are replaced with
+/// value objects, with the default language and direction for strings. This is mostly useful to
+/// compare the output against other JSON-LD implementations.
+pub fn expand(value: &Value, context: &Context) -> Value {
+    let mut state = State::new(false);
+    state.expand = true;
+    Processor::default().process_value_inner(value, context, &mut state)
+        .expect("lenient processing failed")
+}
+
+/// Compact an expanded document, independent of a `Processor`.
+///
+/// This only applies the rewording of the target context to a document that already contains
+/// only absolute IRIs, such as the output of `expand`. The structure of the document is left as
+/// is. Together with `expand`, this splits what a `Processor` does into two separate stages.
+pub fn compact(value: &Value, target: &TargetContext) -> Value {
+    match *value {
+        Value::Array(ref array) => {
+            Value::Array(array.iter().map(|value| compact(value, target)).collect())
+        },
+        Value::Object(ref object) => {
+            let object = object.iter()
+                .map(|(key, value)| {
+                    match key.as_str() {
+                        "@value" => {
+                            (key.clone(), value.clone())
+                        },
+                        "@id" | "@type" => {
+                            (key.clone(), compact_types(value, target))
+                        },
+                        _ if is_keyword(key) => {
+                            (key.clone(), compact(value, target))
+                        },
+                        _ => {
+                            (target.compact_iri(key).into_owned(), compact(value, target))
+                        },
+                    }
+                })
+                .collect();
+            Value::Object(object)
+        },
+        ref value => value.clone(),
+    }
+}
+
+/// Compact the IRIs in an `@id` or `@type` value.
+fn compact_types(value: &Value, target: &TargetContext) -> Value {
+    match *value {
+        Value::String(ref iri) => Value::String(target.compact_iri(iri).into_owned()),
+        Value::Array(ref array) => {
+            Value::Array(array.iter().map(|value| compact_types(value, target)).collect())
+        },
+        ref value => value.clone(),
+    }
+}
+
+/// Convert a processed language map to an array of value objects.
+fn expand_language_map(map: Map, direction: Option<&str>) -> Value {
+    let mut array = Vec::with_capacity(map.len());
+    for (lang, value) in map {
+        for value in OneOrMany::from(&value) {
+            let mut object = Map::with_capacity(2);
+            object.insert("@value".to_owned(), value.clone());
+            if !lang.is_empty() && lang != "@none" {
+                object.insert("@language".to_owned(), Value::String(lang.clone()));
+            }
+            if let Some(direction) = direction {
+                object.insert("@direction".to_owned(), Value::String(direction.to_owned()));
+            }
+            array.push(Value::Object(object));
+        }
+    }
+    Value::Array(array)
+}
+
+/// Wrap a scalar property value in a value object, for expanded form.
+fn expand_scalar(value: Value, context: &Context, direction: Option<&str>) -> Value {
+    let mut object = Map::with_capacity(3);
+    if value.is_string() {
+        if let Some(lang) = context.language() {
+            object.insert("@language".to_owned(), Value::String(lang.to_owned()));
+        }
+        if let Some(direction) = direction {
+            object.insert("@direction".to_owned(), Value::String(direction.to_owned()));
+        }
+    }
+    object.insert("@value".to_owned(), value);
+    Value::Object(object)
+}
+
+/// The value of an option, if it differs from the value in `base`.
+fn option_difference<T: PartialEq + Clone>(value: &Option<T>, base: &Option<T>) -> Option<T> {
+    value.as_ref().filter(|&value| Some(value) != base.as_ref()).cloned()
+}
+
+/// The entries of a map that are not present in `base`, or have a different value.
+fn map_difference<T: PartialEq + Clone>(map: &BTreeMap<String, T>, base: &BTreeMap<String, T>)
+    -> BTreeMap<String, T>
+{
+    map.iter()
+        .filter(|&(key, value)| base.get(key) != Some(value))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+/// Set or remove an entry in a map.
+fn set_entry<T>(map: &mut BTreeMap<String, T>, key: &str, value: Option<T>) {
+    match value {
+        Some(value) => map.insert(key.to_owned(), value),
+        None => map.remove(key),
+    };
+}
+
+/// Keywords that are kept in value objects.
+const VALUE_KEYWORDS: &[&str] = &["@value", "@type", "@language", "@direction", "@index"];
+
+/// Add a type to the `@type` array of a processed node, if it's not already present.
+///
+/// Returns the number of values added to the node.
+fn add_type(node: &mut Map, ty: Value) -> usize {
+    let mut added = 0;
+    let types = node.entry("@type").or_insert_with(|| {
+        added += 1;
+        Value::Array(vec![])
+    });
+    if let Value::Array(ref mut types) = *types {
+        if !types.contains(&ty) {
+            types.insert(0, ty);
+            added += 1;
+        }
+    }
+    added
+}
+
+/// Turn strings in the value of a property with `@type: @id` into node references.
+fn wrap_ids(value: &Value) -> Value {
+    match *value {
+        Value::String(ref id) => {
+            let mut object = Map::with_capacity(1);
+            object.insert("@id".to_owned(), Value::String(id.clone()));
+            Value::Object(object)
+        },
+        Value::Array(ref array) => Value::Array(array.iter().map(wrap_ids).collect()),
+        ref value => value.clone(),
+    }
+}
+
+/// Turn processed node references back into strings, the reverse of `wrap_ids`.
+///
+/// Each reference removes one value from the output, which is added to `removed`.
+fn unwrap_ids(value: Value, removed: &mut usize) -> Value {
+    match value {
+        Value::Object(mut object) if object.len() == 1 && object.contains_key("@id") => {
+            *removed += 1;
+            object.remove("@id").unwrap()
+        },
+        Value::Array(array) => {
+            Value::Array(array.into_iter().map(|value| unwrap_ids(value, removed)).collect())
+        },
+        value => value,
+    }
+}
+
+/// Add an `@context` to the output, if there is one.
+fn add_context(value: Value, context: Option<Value>) -> Value {
+    let context = match context {
+        Some(context) => context,
+        None => return value,
+    };
+    let mut object = match value {
+        Value::Object(object) => object,
+        value => {
+            let mut object = Map::with_capacity(2);
+            object.insert("@graph".to_owned(), value);
+            object
+        },
+    };
+    object.insert("@context".to_owned(), context);
+    Value::Object(object)
+}
+
+/// Keywords that come first in output objects with `Processor::sort_keys`, in order.
+const LEADING_KEYWORDS: &[&str] = &["@context", "@id", "@type"];
+
+/// Order keys in all objects in a value, for `Processor::sort_keys`.
+fn sort_value(value: Value) -> Value {
+    match value {
+        Value::Object(object) => Value::Object(sort_keys(object)),
+        Value::Array(array) => Value::Array(array.into_iter().map(sort_value).collect()),
+        value => value,
+    }
+}
+
+/// Order keys in an object and all objects nested in it, for `Processor::sort_keys`.
+fn sort_keys(object: Map) -> Map {
+    let mut entries = object.into_iter()
+        .map(|(key, value)| (key, sort_value(value)))
+        .collect::<Vec<_>>();
+    entries.sort_by(|(a, _), (b, _)| {
+        let rank = |key: &str| {
+            LEADING_KEYWORDS.iter().position(|keyword| *keyword == key)
+                .unwrap_or(LEADING_KEYWORDS.len() + !is_keyword(key) as usize)
+        };
+        rank(a).cmp(&rank(b)).then_with(|| a.cmp(b))
+    });
+    entries.into_iter().collect()
+}
+
+/// Create an `@context` value defining the generated prefixes and coerced properties of a
+/// processing run, if there are any.
+fn output_context(state: &mut State) -> Option<Value> {
+    if state.prefixes.is_empty() && state.coercions.is_empty() {
+        return None;
+    }
+    let prefixes = state.prefixes.drain(..)
+        .map(|(prefix, base)| (prefix, Value::String(base)));
+    let coercions = state.coercions.drain(..)
+        .map(|(name, iri)| {
+            let mut definition = Map::with_capacity(2);
+            definition.insert("@id".to_owned(), Value::String(iri));
+            definition.insert("@type".to_owned(), Value::String("@id".to_owned()));
+            (name, Value::Object(definition))
+        });
+    Some(Value::Object(prefixes.chain(coercions).collect()))
+}
+
+/// Count the JSON values in a value, including the value itself.
+fn value_size(value: &Value) -> usize {
+    match *value {
+        Value::Array(ref array) => 1 + array.iter().map(value_size).sum::<usize>(),
+        Value::Object(ref object) => 1 + object.values().map(value_size).sum::<usize>(),
+        _ => 1,
+    }
+}
+
+/// Append keys of an object that are not yet in the list.
+fn record_order(order: &mut Vec<String>, object: &Map) {
+    for key in object.keys() {
+        if !order.contains(key) {
+            order.push(key.clone());
+        }
+    }
+}
+
+/// Merge nodes with the same `@id`, keeping the order of first occurrence.
+fn merge_nodes(nodes: Vec<Map>) -> Vec<Map> {
+    let mut index = HashMap::new();
+    let mut result: Vec<Map> = Vec::with_capacity(nodes.len());
+    for node in nodes {
+        let id = node.get("@id").and_then(Value::as_str).map(str::to_owned);
+        let position = id.as_ref().and_then(|id| index.get(id)).cloned();
+        match position {
+            Some(position) => {
+                let target: &mut Map = &mut result[position];
+                for (key, value) in node {
+                    match target.get_mut(&key) {
+                        Some(existing) => merge_values(existing, value),
+                        None => {
+                            target.insert(key, value);
+                        },
+                    }
+                }
+            },
+            None => {
+                if let Some(id) = id {
+                    index.insert(id, result.len());
+                }
+                result.push(node);
+            },
+        }
+    }
+    result
+}
+
+/// Add values to a property of a merged node, turning it into an array if necessary.
+fn merge_values(existing: &mut Value, values: Value) {
+    for value in OneOrMany::from(&values) {
+        if let Value::Array(ref mut array) = *existing {
+            if !array.contains(value) {
+                array.push(value.clone());
+            }
+        } else if *existing != *value {
+            let first = mem::replace(existing, Value::Null);
+            *existing = Value::Array(vec![first, value.clone()]);
+        }
+    }
+}
+
+/// Remove flattened nodes with a blank node identifier, and references to them.
+fn strip_blank_nodes(nodes: &mut Vec<Map>) {
+    nodes.retain(|node| !is_blank_reference(node));
+    for node in nodes {
+        strip_references(node);
+    }
+}
+
+/// Remove references to blank nodes from an object and all objects nested in it. Properties left
+/// with an empty array are removed as well.
+fn strip_references(object: &mut Map) {
+    object.retain(|_, value| match *value {
+        Value::Array(ref mut array) if !array.is_empty() => {
+            array.retain(|value| !value.as_object().is_some_and(is_blank_reference));
+            for value in array.iter_mut() {
+                if let Value::Object(ref mut object) = *value {
+                    strip_references(object);
+                }
+            }
+            !array.is_empty()
+        },
+        Value::Object(ref mut object) => {
+            strip_references(object);
+            !is_blank_reference(object)
+        },
+        _ => true,
+    });
+}
+
+/// Whether an object has a blank node identifier.
+fn is_blank_reference(object: &Map) -> bool {
+    object.get("@id").and_then(Value::as_str).is_some_and(is_blank_node)
+}
+
+/// Replace identifiers in an object and all objects nested in it.
+fn relabel_ids(object: &mut Map, labels: &HashMap<String, String>) {
+    for (key, value) in object.iter_mut() {
+        match *value {
+            Value::String(ref mut id) if key == "@id" => {
+                if let Some(label) = labels.get(id) {
+                    *id = label.clone();
+                }
+            },
+            Value::Array(ref mut array) => {
+                for value in array {
+                    if let Value::Object(ref mut object) = *value {
+                        relabel_ids(object, labels);
+                    }
+                }
+            },
+            Value::Object(ref mut object) => {
+                relabel_ids(object, labels);
+            },
+            _ => {},
+        }
     }
 }
 
@@ -455,12 +3156,76 @@ fn is_keyword(input: &str) -> bool {
     input.starts_with('@')
 }
 
-/// Whether the input is a valid absolute IRI.
+/// Whether the input is a valid base direction.
+fn is_direction(input: &str) -> bool {
+    input == "ltr" || input == "rtl"
+}
+
+/// Whether a value is valid for `@direction`, which may be null to clear it.
+fn is_direction_value(value: &Value) -> bool {
+    value.as_str().is_some_and(is_direction) || value.is_null()
+}
+
+/// Decode percent-escapes of unreserved characters, and uppercase the hexadecimal digits of other
+/// percent-escapes.
+fn normalize_percent(input: &str) -> Cow<'_, str> {
+    let bytes = input.as_bytes();
+    let is_escape = |index: usize| {
+        bytes[index] == b'%' && bytes.get(index + 1).is_some_and(u8::is_ascii_hexdigit)
+            && bytes.get(index + 2).is_some_and(u8::is_ascii_hexdigit)
+    };
+    let needs_change = (0..bytes.len()).any(|index| {
+        is_escape(index) && (input[index + 1..index + 3].bytes().any(|b| b.is_ascii_lowercase())
+            || is_unreserved(u8::from_str_radix(&input[index + 1..index + 3], 16).unwrap()))
+    });
+    if !needs_change {
+        return Cow::from(input);
+    }
+
+    let mut output = String::with_capacity(input.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if is_escape(index) {
+            let byte = u8::from_str_radix(&input[index + 1..index + 3], 16).unwrap();
+            if is_unreserved(byte) {
+                output.push(byte as char);
+            } else {
+                output.push_str(&format!("%{:02X}", byte));
+            }
+            index += 3;
+        } else {
+            let c = input[index..].chars().next().unwrap();
+            output.push(c);
+            index += c.len_utf8();
+        }
+    }
+    Cow::from(output)
+}
+
+/// Whether a byte is an unreserved character in an IRI.
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'.' || byte == b'_' || byte == b'~'
+}
+
+/// Whether the input is a valid absolute IRI, or a blank node identifier.
+///
+/// Only the scheme is checked, which must start with a letter, followed by letters, digits, `+`,
+/// `-` or `.`. Schemes are case-insensitive, so letters may be in either case.
 fn is_absolute_iri(input: &str) -> bool {
-    input.contains(':') && !input.starts_with('@')
+    match input.find(':') {
+        Some(index) => iri::is_scheme(&input[..index]) || is_blank_node(input),
+        None => false,
+    }
+}
+
+/// Whether the input is a blank node identifier.
+fn is_blank_node(input: &str) -> bool {
+    input.starts_with("_:")
 }
 
 /// Whether the input is a valid CURIE prefix.
+///
+/// The `_` prefix is reserved for blank node identifiers.
 fn is_curie_prefix(input: &str) -> bool {
-    !input.is_empty() && !input.contains(':') && !input.starts_with('@')
+    !input.is_empty() && input != "_" && !input.contains(':') && !input.starts_with('@')
 }
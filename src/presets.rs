@@ -0,0 +1,46 @@
+//! Preset contexts for well-known vocabularies, enabled with the `presets` feature.
+
+use {Context, TargetContext};
+
+/// Prefixes of well-known vocabularies, and their base IRIs.
+const COMMON_PREFIXES: &[(&str, &str)] = &[
+    ("dc", "http://purl.org/dc/terms/"),
+    ("foaf", "http://xmlns.com/foaf/0.1/"),
+    ("owl", "http://www.w3.org/2002/07/owl#"),
+    ("rdf", "http://www.w3.org/1999/02/22-rdf-syntax-ns#"),
+    ("rdfs", "http://www.w3.org/2000/01/rdf-schema#"),
+    ("schema", "http://schema.org/"),
+    ("skos", "http://www.w3.org/2004/02/skos/core#"),
+    ("xsd", "http://www.w3.org/2001/XMLSchema#"),
+];
+
+impl Context {
+    /// A context defining prefixes for well-known vocabularies.
+    ///
+    /// The prefixes are `dc` (DCMI terms), `foaf`, `owl`, `rdf`, `rdfs`, `schema`, `skos` and
+    /// `xsd`.
+    pub fn common_prefixes() -> Context {
+        let mut context = Context::new();
+        for &(prefix, base) in COMMON_PREFIXES {
+            context.add_prefix(prefix, base);
+        }
+        context
+    }
+
+    /// A context with schema.org as the default namespace, plus the prefixes of
+    /// `Context::common_prefixes`.
+    pub fn schema_org() -> Context {
+        let mut context = Context::common_prefixes();
+        context.with_vocab("http://schema.org/");
+        context
+    }
+}
+
+impl TargetContext {
+    /// A target context with rules for the same prefixes as `Context::common_prefixes`.
+    pub fn common_prefixes() -> TargetContext {
+        COMMON_PREFIXES.iter()
+            .map(|&(prefix, base)| (prefix.to_owned(), base.to_owned()))
+            .collect()
+    }
+}
@@ -0,0 +1,255 @@
+//! Conversion of documents to RDF.
+
+use json::{self, Value};
+use std::fmt;
+use {is_blank_node, Map, Processor, State, TargetContext};
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const RDF_JSON: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#JSON";
+const XSD_BOOLEAN: &str = "http://www.w3.org/2001/XMLSchema#boolean";
+const XSD_DOUBLE: &str = "http://www.w3.org/2001/XMLSchema#double";
+const XSD_INTEGER: &str = "http://www.w3.org/2001/XMLSchema#integer";
+
+/// A single RDF statement, produced by `Processor::triples`.
+///
+/// The `Display` implementation formats the triple as an N-Triples line, without line ending.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Triple {
+    /// The node the statement is about.
+    pub subject: Subject,
+    /// The absolute IRI of the property.
+    pub predicate: String,
+    /// The value of the property.
+    pub object: Object,
+}
+
+/// The subject of a `Triple`.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Subject {
+    /// A node with an absolute IRI.
+    Iri(String),
+    /// A node with a blank node identifier, including the `_:` prefix.
+    BlankNode(String),
+}
+
+/// The object of a `Triple`.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub enum Object {
+    /// A reference to a node with an absolute IRI.
+    Iri(String),
+    /// A reference to a node with a blank node identifier, including the `_:` prefix.
+    BlankNode(String),
+    /// A literal value.
+    Literal(Literal),
+}
+
+/// A literal value in a `Triple`.
+#[derive(Clone,Debug,PartialEq,Eq)]
+pub struct Literal {
+    /// The lexical form of the value.
+    pub value: String,
+    /// The absolute IRI of the datatype. `None` for plain strings, with or without a language.
+    pub datatype: Option<String>,
+    /// The language tag, if any.
+    pub language: Option<String>,
+}
+
+impl Processor {
+    /// Process a value, and return the RDF triples it contains.
+    ///
+    /// The document is flattened first, so every node becomes a subject, and nodes without an
+    /// `@id` are given a blank node identifier. The target context and other output settings are
+    /// not used, because RDF requires absolute IRIs. Properties that are blank node identifiers
+    /// can't be predicates, and are skipped.
+    pub fn triples(&self, value: &Value) -> impl Iterator<Item = Triple> {
+        let processor = Processor {
+            target: TargetContext::default(),
+            auto_prefix: None,
+            type_aliases: false,
+            wrap_strings: false,
+            order_key: None,
+            ..self.clone()
+        };
+        let mut state = State::new(false);
+        state.expand = true;
+
+        let mut triples = Vec::new();
+        for node in processor.flatten_nodes(value, &mut state) {
+            node_triples(&node, &mut triples);
+        }
+        triples.into_iter()
+    }
+
+    /// Process a value, and serialize the triples it contains as N-Triples.
+    ///
+    /// See `triples` for details.
+    pub fn to_ntriples(&self, value: &Value) -> String {
+        self.triples(value)
+            .map(|triple| format!("{}\n", triple))
+            .collect()
+    }
+}
+
+/// Collect the triples of a flattened node in expanded form.
+fn node_triples(node: &Map, triples: &mut Vec<Triple>) {
+    let subject = match node.get("@id").and_then(Value::as_str) {
+        Some(id) if is_blank_node(id) => Subject::BlankNode(id.to_owned()),
+        Some(id) => Subject::Iri(id.to_owned()),
+        None => return,
+    };
+    // Sort properties, so the output doesn't depend on the `preserve_order` feature.
+    let mut properties = node.iter().collect::<Vec<_>>();
+    properties.sort_by_key(|&(key, _)| key);
+    for (key, values) in properties {
+        match key.as_str() {
+            "@type" => {
+                for ty in values.as_array().into_iter().flatten().filter_map(Value::as_str) {
+                    triples.push(Triple {
+                        subject: subject.clone(),
+                        predicate: RDF_TYPE.to_owned(),
+                        object: reference(ty),
+                    });
+                }
+            },
+            key if key.starts_with('@') || is_blank_node(key) => {},
+            predicate => {
+                let mut objects = Vec::new();
+                value_objects(values, &mut objects);
+                for object in objects {
+                    triples.push(Triple {
+                        subject: subject.clone(),
+                        predicate: predicate.to_owned(),
+                        object,
+                    });
+                }
+            },
+        }
+    }
+}
+
+/// Collect the objects for the values of a property, which may be nested arrays.
+fn value_objects(value: &Value, objects: &mut Vec<Object>) {
+    let object = match *value {
+        Value::Array(ref array) => {
+            for value in array {
+                value_objects(value, objects);
+            }
+            return;
+        },
+        Value::Object(ref object) => {
+            if let Some(id) = object.get("@id").and_then(Value::as_str) {
+                Some(reference(id))
+            } else if let Some(value) = object.get("@value") {
+                let datatype = object.get("@type").and_then(Value::as_str);
+                let language = object.get("@language").and_then(Value::as_str);
+                literal(value, datatype, language)
+            } else {
+                None
+            }
+        },
+        ref value => literal(value, None, None),
+    };
+    objects.extend(object);
+}
+
+/// Create a reference to a node.
+fn reference(id: &str) -> Object {
+    if is_blank_node(id) {
+        Object::BlankNode(id.to_owned())
+    } else {
+        Object::Iri(id.to_owned())
+    }
+}
+
+/// Create a literal, returning `None` for values that can't be literals.
+fn literal(value: &Value, datatype: Option<&str>, language: Option<&str>) -> Option<Object> {
+    let (lexical, default_datatype) = match *value {
+        _ if datatype == Some("@json") => (json::to_string(value).ok()?, None),
+        Value::String(ref string) => (string.clone(), None),
+        Value::Bool(value) => (value.to_string(), Some(XSD_BOOLEAN)),
+        Value::Number(ref number) if number.is_f64() => {
+            (format!("{:E}", number.as_f64()?), Some(XSD_DOUBLE))
+        },
+        Value::Number(ref number) => (number.to_string(), Some(XSD_INTEGER)),
+        _ => return None,
+    };
+    let datatype = match datatype {
+        Some("@json") => Some(RDF_JSON),
+        datatype => datatype.or(default_datatype),
+    };
+    Some(Object::Literal(Literal {
+        value: lexical,
+        datatype: datatype.map(str::to_owned),
+        language: language.filter(|_| datatype.is_none()).map(str::to_owned),
+    }))
+}
+
+impl fmt::Display for Triple {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {} .", self.subject, IriRef(&self.predicate), self.object)
+    }
+}
+
+impl fmt::Display for Subject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Subject::Iri(ref iri) => write!(f, "{}", IriRef(iri)),
+            Subject::BlankNode(ref id) => write!(f, "{}", id),
+        }
+    }
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Object::Iri(ref iri) => write!(f, "{}", IriRef(iri)),
+            Object::BlankNode(ref id) => write!(f, "{}", id),
+            Object::Literal(ref literal) => write!(f, "{}", literal),
+        }
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "\"")?;
+        for c in self.value.chars() {
+            match c {
+                '"' => write!(f, "\\\"")?,
+                '\\' => write!(f, "\\\\")?,
+                '\n' => write!(f, "\\n")?,
+                '\r' => write!(f, "\\r")?,
+                '\t' => write!(f, "\\t")?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        write!(f, "\"")?;
+        if let Some(ref datatype) = self.datatype {
+            write!(f, "^^{}", IriRef(datatype))
+        } else if let Some(ref language) = self.language {
+            write!(f, "@{}", language)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// An IRI formatted for N-Triples, between angle brackets.
+///
+/// Characters that N-Triples doesn't allow in an IRI are written as `\u` escapes. The result is
+/// valid N-Triples, even if the IRI itself is not valid.
+struct IriRef<'a>(&'a str);
+
+impl<'a> fmt::Display for IriRef<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<")?;
+        for c in self.0.chars() {
+            match c {
+                '\u{0}'..='\u{20}' | '<' | '>' | '"' | '{' | '}' | '|' | '^' | '`' | '\\' => {
+                    write!(f, "\\u{:04X}", c as u32)?;
+                },
+                c => write!(f, "{}", c)?,
+            }
+        }
+        write!(f, ">")
+    }
+}
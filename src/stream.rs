@@ -0,0 +1,64 @@
+//! Processing of documents read from streams and files.
+
+use json::{self, Value};
+use json::de::{IoRead, StreamDeserializer};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::Path;
+use {JsonNsError, Processor, ProcessorSession};
+
+/// An iterator over processed documents read from a stream, created with
+/// `Processor::process_reader`.
+pub struct ProcessReader<'a, R: io::Read> {
+    session: ProcessorSession<'a>,
+    documents: StreamDeserializer<'static, IoRead<R>, Value>,
+}
+
+impl Processor {
+    /// Read documents from a stream, and process them one at a time.
+    ///
+    /// The input is a sequence of JSON values, optionally separated by whitespace, such as
+    /// newline-delimited JSON. Each document is processed starting from the external context in
+    /// this struct, like in `process_documents`, but only one document is held in memory at a
+    /// time. A single top-level array is one document, and is not split.
+    ///
+    /// Processing uses a session, see `Processor::session`. The iterator yields an error if the
+    /// input could not be parsed, after which it should not be used further.
+    pub fn process_reader<R: io::Read>(&self, reader: R) -> ProcessReader<'_, R> {
+        ProcessReader {
+            session: self.session(),
+            documents: json::Deserializer::from_reader(reader).into_iter(),
+        }
+    }
+
+    /// Read a single document from a file, and process it like `process_value`.
+    ///
+    /// Errors opening or reading the file are returned as-is. If the file does not contain valid
+    /// JSON, the error is of kind `InvalidData`, or `UnexpectedEof` if the input ends early, and
+    /// wraps the `serde_json::Error`.
+    pub fn process_file<P: AsRef<Path>>(&self, path: P) -> io::Result<Value> {
+        let file = File::open(path)?;
+        let document: Value = json::from_reader(BufReader::new(file))?;
+        Ok(self.process_value(&document))
+    }
+}
+
+impl<'a, R: io::Read> Iterator for ProcessReader<'a, R> {
+    type Item = Result<Value, JsonNsError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let document = self.documents.next()?;
+        Some(document
+            .map(|document| self.session.process_value(&document))
+            .map_err(JsonNsError::from))
+    }
+}
+
+impl<'a, R: io::Read> fmt::Debug for ProcessReader<'a, R> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ProcessReader")
+            .field("session", &self.session)
+            .finish_non_exhaustive()
+    }
+}
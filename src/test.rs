@@ -1,10 +1,44 @@
-use ::{Context, Processor, TargetContext};
+use ::{compact, expand, AutoPrefix, CompactStrategy, Context, ContextLoader, Dropped, JsonNsError};
+use ::{Limit, Literal, Lowercase, Name, Object, PrefixRedefinition, Processor, Subject};
+use ::{TargetContext, Triple};
+use ::{iri, is_absolute_iri, UnknownPrefix, Warning};
+use ::util::OneOrMany;
 use colored::Colorize;
 use json::{self, Value};
+use std::collections::{BTreeMap, HashSet};
 use std::ffi::OsStr;
 use std::fs::{File, read_dir};
-use std::io::Read;
+use std::io::{self, Read};
 use std::path::PathBuf;
+use std::sync::Arc;
+
+// A loader serving remote contexts from memory.
+#[derive(Debug,Default)]
+struct MapLoader(BTreeMap<String, Value>);
+
+impl ContextLoader for MapLoader {
+    fn load(&self, iri: &str) -> io::Result<Value> {
+        self.0.get(iri).cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such context"))
+    }
+}
+
+// Create a loader serving the remote contexts used by the test files. Each file in
+// `tests/contexts` is served as `http://example.com/contexts/<file stem>`.
+fn contexts_loader() -> MapLoader {
+    let mut loader = MapLoader::default();
+    for entry in read_dir("tests/contexts").expect("could not read contexts dir") {
+        let path = entry.expect("could not iterate contexts dir").path();
+        let stem = path.file_stem().and_then(|s| s.to_str())
+            .expect("context has invalid filename");
+        let file = File::open(&path)
+            .expect("could not open context");
+        let value = json::from_reader(file)
+            .expect("context has invalid JSON");
+        loader.0.insert(format!("http://example.com/contexts/{}", stem), value);
+    }
+    loader
+}
 
 // Parse a target context specification.
 fn parse_target(input: &str) -> Result<TargetContext, ()> {
@@ -35,9 +69,11 @@ fn run_dir(dir: &str) {
         .collect();
     entries.sort_unstable();
 
+    let loader = Arc::new(contexts_loader());
     let mut num_passed = 0;
     for path in &entries {
         let mut processor = Processor::new();
+        processor.loader = Some(loader.clone());
         let mut data = String::new();
         File::open(path)
             .expect("could not open test")
@@ -88,3 +124,2100 @@ fn run_dir(dir: &str) {
 fn test() {
     run_dir("tests");
 }
+
+#[test]
+fn validate_target() {
+    let mut target = TargetContext::new();
+    target.add_rule("", "http://example.com/vocab#");
+    target.add_rule("ex", "http://example.com/ns#");
+    assert!(target.validate().is_empty());
+
+    target.add_rule("ex", "http://example.com/other#");
+    target.add_rule("", "relative/");
+    target.add_rule("a:b", "http://example.com/ab#");
+    target.add_rule("_", "_:");
+    assert_eq!(target.validate(), vec![
+        Warning::DuplicatePrefix("/2/0".to_owned()),
+        Warning::DuplicatePrefix("/3/0".to_owned()),
+        Warning::InvalidIri("/3/1".to_owned()),
+        Warning::InvalidDefinition("/4/0".to_owned()),
+        Warning::InvalidDefinition("/5/0".to_owned()),
+    ]);
+}
+
+#[test]
+fn target_from_iter() {
+    let rules = vec![
+        ("ex".to_owned(), "http://example.com/ns#".to_owned()),
+        ("".to_owned(), "http://example.com/".to_owned()),
+    ];
+    let mut target: TargetContext = rules.clone().into_iter().collect();
+    assert_eq!(target.rules, rules);
+    assert_eq!(target.compact_iri("http://example.com/ns#foo"), "ex:foo");
+
+    target.extend(vec![("other".to_owned(), "http://example.org/".to_owned())]);
+    assert_eq!(target.rules.len(), 3);
+    assert_eq!(target.rules[2].0, "other");
+    assert_eq!(target.compact_iri("http://example.org/foo"), "other:foo");
+
+    let mut processor = Processor::with_context(Context::new()).with_target(target.clone());
+    assert_eq!(processor.rules(), &target.rules[..]);
+    processor.rules_mut().retain(|(prefix, _)| prefix == "other");
+    assert_eq!(processor.rules().len(), 1);
+    assert_eq!(processor.process_value(&json!({ "http://example.org/foo": 1 })), json!({
+        "other:foo": 1
+    }));
+
+    target.add_rule("ex", "http://example.org/ns#");
+    assert_eq!(target.remove_rule("ex"), 2);
+    assert_eq!(target.remove_rule("ex"), 0);
+    assert_eq!(target.compact_iri("http://example.com/ns#foo"), "ns#foo");
+    target.clear_rules();
+    assert!(target.rules.is_empty());
+}
+
+#[test]
+fn compact_strategy() {
+    let mut target = TargetContext::new();
+    target
+        .add_rule("ex", "http://example.com/vocab#ex/")
+        .add_rule("", "http://example.com/vocab#");
+    assert_eq!(target.compact_iri("http://example.com/vocab#ex/foo"), "ex:foo");
+    assert_eq!(target.compact_iri("http://example.com/vocab#foo"), "foo");
+
+    target.strategy = CompactStrategy::PreferDefault;
+    assert_eq!(target.compact_iri("http://example.com/vocab#ex/foo"), "ex/foo");
+    assert_eq!(target.compact_iri("http://example.com/vocab#foo"), "foo");
+    assert_eq!(target.compact_iri("http://example.org/foo"), "http://example.org/foo");
+}
+
+#[test]
+fn compact_name() {
+    let context = Context::from(&json!({
+        "schema": "http://schema.org/",
+        "name": { "@id": "http://schema.org/name" }
+    }));
+    let mut target = TargetContext::new();
+    target.add_rule("", "http://example.com/vocab#").add_rule("schema", "http://schema.org/");
+    for (term, iri) in &context.aliases {
+        target.add_term(term, iri);
+    }
+    target.add_term("id", "http://example.org/identifier");
+
+    assert_eq!(target.compact_name("http://schema.org/name"), "name");
+    assert_eq!(target.compact_iri("http://schema.org/name"), "schema:name");
+    assert_eq!(target.compact_name("http://schema.org/email"), "schema:email");
+    assert_eq!(target.compact_name("http://example.com/vocab#foo"), "foo");
+    assert_eq!(target.compact_name("http://example.org/identifier"), "id");
+    assert_eq!(target.compact_name("http://example.org/other"), "http://example.org/other");
+}
+
+#[test]
+fn normalize_percent() {
+    let mut target = TargetContext::new();
+    target
+        .add_rule("ex", "http://example.com/~ns/")
+        .add_rule("other", "http://example.com/a%2fb/");
+    assert_eq!(target.compact_iri("http://example.com/%7ens/foo"), "http://example.com/%7ens/foo");
+
+    target.normalize_percent = true;
+    assert_eq!(target.compact_iri("http://example.com/%7ens/foo"), "ex:foo");
+    assert_eq!(target.compact_iri("http://example.com/%7Ens/f%6Fo"), "ex:foo");
+    assert_eq!(target.compact_iri("http://example.com/~ns/a%2fb"), "ex:a%2Fb");
+    assert_eq!(target.compact_iri("http://example.com/a%2Fb/foo"), "other:foo");
+    assert_eq!(target.compact_iri("http://example.com/a/b/foo"), "http://example.com/a/b/foo");
+    assert_eq!(target.compact_iri("http://example.com/%zz/%"), "http://example.com/%zz/%");
+}
+
+#[test]
+fn context_builder() {
+    let mut context = Context::new();
+    context
+        .with_vocab("http://example.com/vocab#")
+        .with_language("en")
+        .add_prefix("ex", "http://example.com/ns#")
+        .add_alias("name", "ex:title")
+        .add_container("name", "@language");
+
+    assert_eq!(context.vocab(), Some("http://example.com/vocab#"));
+    assert_eq!(context.language(), Some("en"));
+    assert_eq!(context.prefix("ex"), Some("http://example.com/ns#"));
+    assert_eq!(context.alias("name"), Some("ex:title"));
+    assert_eq!(context.container_of("name"), Some("@language"));
+    assert_eq!(context.prefix("name"), None);
+    assert_eq!(Context::new().language(), None);
+
+    let prefixes: Vec<_> = (&context).into_iter().collect();
+    assert_eq!(prefixes, vec![("ex", "http://example.com/ns#")]);
+    let target: TargetContext = context.iter_prefixes()
+        .map(|(prefix, base)| (prefix.to_owned(), base.to_owned()))
+        .collect();
+    assert_eq!(target.compact_iri("http://example.com/ns#foo"), "ex:foo");
+
+    let processor = Processor::with_context(context);
+    let output = processor.process_value(&json!({
+        "foo": 1,
+        "ex:bar": 2,
+        "name": "Hello"
+    }));
+    assert_eq!(output, json!({
+        "http://example.com/vocab#foo": 1,
+        "http://example.com/ns#bar": 2,
+        "http://example.com/ns#title": { "en": "Hello" }
+    }));
+}
+
+#[test]
+fn terms_for() {
+    let context = Context::from(&json!({
+        "@vocab": "http://example.com/vocab#",
+        "ex": "http://example.com/",
+        "vocab": "http://example.com/vocab#",
+        "title": { "@id": "name" },
+        "label": { "@id": "http://example.com/vocab#name" },
+        "other": { "@id": "ex:other" },
+        "type": { "@id": "@type" }
+    }));
+    assert_eq!(context.terms_for("http://example.com/vocab#name"), vec![
+        "label", "title", "ex", "vocab", "",
+    ]);
+    assert_eq!(context.terms_for("http://example.com/other"), vec!["other", "ex"]);
+    assert!(context.terms_for("http://example.org/").is_empty());
+}
+
+#[test]
+fn context_len() {
+    let mut context = Context::new();
+    context.unknown_prefix = UnknownPrefix::Drop;
+    assert!(context.is_empty());
+    assert_eq!(context.len(), 0);
+
+    context.merge_value(&json!({
+        "@vocab": "http://example.com/vocab#",
+        "@language": "en",
+        "ex": "http://example.com/ns#",
+        "label": { "@id": "title", "@container": ["@language", "@set"] }
+    }));
+    assert!(!context.is_empty());
+    // The container mapping and `@set` of `label` are also recorded under its absolute IRI.
+    assert_eq!(context.len(), 8);
+
+    context.merge_value(&Value::Null);
+    assert!(context.is_empty());
+}
+
+#[test]
+fn one_or_many() {
+    let array = json!(["a", ["b"], null]);
+    let items: Vec<&Value> = OneOrMany::from(&array).collect();
+    assert_eq!(items, vec![&array[0], &array[1], &array[2]]);
+    assert!(std::ptr::eq(items[0], &array[0]));
+
+    let single = json!({ "a": 1 });
+    assert_eq!(OneOrMany::from(&single).collect::<Vec<_>>(), vec![&single]);
+    assert_eq!(OneOrMany::from(&Value::Null).count(), 1);
+    assert_eq!(OneOrMany::from(&json!([])).count(), 0);
+}
+
+#[test]
+fn merge_str() {
+    let mut context = Context::new();
+    context.merge_str(r#"{ "@vocab": "http://example.com/vocab#" }"#).unwrap();
+    context.merge_str(r#"[{ "ex": "http://example.com/ns#" }]"#).unwrap();
+    assert!(context.merge_str(r#"{ "@language": "#).is_err());
+    assert_eq!(context, Context::from(&json!({
+        "@vocab": "http://example.com/vocab#",
+        "ex": "http://example.com/ns#"
+    })));
+}
+
+#[test]
+fn merge_contexts() {
+    let mut context = Context::from(&json!({
+        "@vocab": "http://example.com/vocab#",
+        "@language": "en",
+        "ex": "http://example.com/ns#",
+        "name": { "@id": "ex:name", "@container": "@language" },
+        "fixed": { "@id": "ex:fixed", "@protected": true }
+    }));
+    let overlay = json!({
+        "@language": "de",
+        "ex": "http://example.com/other#",
+        "name": "http://example.com/name/",
+        "fixed": "http://example.com/fixed/",
+        "extra": { "@id": "ex:extra", "@protected": true }
+    });
+
+    // The result is the same as merging the overlay as JSON. The definition of `name` is
+    // extended, while the protected term `fixed` is kept.
+    let mut expected = context.clone();
+    expected.merge_value(&overlay);
+    context.merge(&Context::from(&overlay));
+    assert_eq!(context, expected);
+    assert_eq!(context.ns.as_deref(), Some("http://example.com/vocab#"));
+    assert_eq!(context.language(), Some("de"));
+    assert_eq!(context.container_of("name"), Some("@language"));
+    assert_eq!(context.alias("fixed"), Some("ex:fixed"));
+    assert!(context.protected.contains("extra"));
+}
+
+#[test]
+fn container_by_iri() {
+    let mut context = Context::from(&json!({
+        "@vocab": "http://example.com/vocab#",
+        "tags": { "@container": ["@index", "@set"] }
+    }));
+    assert_eq!(context.container_of("http://example.com/vocab#tags"), Some("@index"));
+    assert!(context.sets.contains("http://example.com/vocab#tags"));
+
+    context.merge_value(&json!({ "tags": null }));
+    assert!(context.container.is_empty() && context.sets.is_empty());
+}
+
+#[test]
+fn context_difference() {
+    let base = Context::from(&json!({
+        "@vocab": "http://example.com/vocab#",
+        "@language": "en",
+        "ex": "http://example.com/ns#",
+        "name": { "@id": "ex:name", "@container": "@language" },
+        "tags": { "@id": "ex:tags", "@container": "@set" }
+    }));
+    let mut context = base.clone();
+    context.merge_value(&json!({
+        "@language": "de",
+        "@base": "http://example.com/doc",
+        "ex": "http://example.com/ns#",
+        "name": { "@id": "ex:title" },
+        "other": "http://example.com/other#"
+    }));
+
+    let difference = context.difference(&base);
+    assert_eq!(difference, Context::from(&json!({
+        "@language": "de",
+        "@base": "http://example.com/doc",
+        "name": { "@id": "ex:title" },
+        "other": "http://example.com/other#",
+        "http://example.com/ns#title": { "@container": "@language" }
+    })));
+    assert!(base.difference(&base).is_empty());
+
+    let mut merged = base.clone();
+    merged.merge(&difference);
+    assert_eq!(merged, context);
+}
+
+#[test]
+fn prefix_redefinition() {
+    let input = json!({
+        "@context": [
+            { "ex": "http://example.com/first#", "other": "http://example.com/other#" },
+            { "ex": "http://example.com/first#" },
+            { "ex": { "@id": "http://example.com/second#", "@prefix": true } }
+        ],
+        "ex:foo": "a"
+    });
+
+    let mut processor = Processor::new();
+    processor.context.prefix_redefinition = PrefixRedefinition::Keep;
+    assert_eq!(processor.process_value(&input), json!({ "http://example.com/first#foo": "a" }));
+    match processor.process_value_strict(&input) {
+        Err(JsonNsError::PrefixRedefined(ref prefix)) if prefix == "ex" => {},
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // Prefixes may be defined again after clearing them.
+    let mut context = Context::from(&json!({ "ex": "http://example.com/first#" }));
+    context.prefix_redefinition = PrefixRedefinition::Keep;
+    context.merge(&Context::from(&json!({ "ex": "http://example.com/second#" })));
+    assert_eq!(context.prefixes["ex"], "http://example.com/first#");
+    context.merge_value(&json!([{ "ex": null }, { "ex": "http://example.com/second#" }]));
+    assert_eq!(context.prefixes["ex"], "http://example.com/second#");
+}
+
+#[test]
+fn clear_context() {
+    let value = json!({
+        "@vocab": "http://example.com/vocab#",
+        "@language": "en",
+        "ex": "http://example.com/ns#",
+        "name": { "@id": "ex:name", "@container": ["@language", "@set"], "@protected": true }
+    });
+    let mut context = Context::from(&value);
+    context.clear_prefixes();
+    assert_eq!(context.prefix("ex"), None);
+    assert_eq!(context.alias("name"), Some("ex:name"));
+    context.clear_containers();
+    assert!(context.container.is_empty() && context.sets.is_empty());
+    context.clear_aliases();
+    assert_eq!(context.len(), 2);
+
+    context.merge_value(&value);
+    context.unknown_prefix = UnknownPrefix::Drop;
+    context.clear();
+    assert_eq!(context, Context::default());
+}
+
+#[test]
+fn context_equality() {
+    let a = Context::from(&json!({
+        "@vocab": "http://example.com/vocab#",
+        "ex": "http://example.com/ns#",
+        "name": { "@id": "ex:title", "@context": { "@language": "en" } }
+    }));
+    let b = Context::from(&json!([
+        { "name": { "@id": "ex:title", "@context": { "@language": "en" } } },
+        { "ex": "http://example.com/ns#", "@vocab": "http://example.com/vocab#" }
+    ]));
+    assert_eq!(a, b);
+
+    let mut c = b.clone();
+    c.add_alias("name", "ex:name");
+    assert_ne!(a, c);
+
+    let cache = vec![a, c].into_iter().collect::<HashSet<_>>();
+    assert!(cache.contains(&b));
+    assert_eq!(cache.len(), 2);
+}
+
+// Serve a single HTTP response on a local port after a delay, and return the URL.
+#[cfg(feature = "http")]
+fn serve_once(body: &'static str, delay: std::time::Duration) -> String {
+    use std::io::Write;
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("could not bind listener");
+    let url = format!("http://{}/context", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("could not accept connection");
+        let _ = stream.read(&mut [0; 4096]);
+        std::thread::sleep(delay);
+        let _ = write!(stream, concat!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/ld+json\r\n",
+            "Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+        ), body.len(), body);
+    });
+    url
+}
+
+// Create an HTTP loader that ignores proxy settings, to reach the local server.
+#[cfg(feature = "http")]
+fn http_loader() -> ::HttpLoader {
+    let client = ::reqwest::blocking::Client::builder().no_proxy().build()
+        .expect("could not create client");
+    ::HttpLoader::with_client(client)
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn http_loader_max_size() {
+    use std::time::Duration;
+
+    let body = r#"{ "@context": { "@vocab": "http://example.com/vocab#" } }"#;
+    let mut loader = http_loader();
+    let url = serve_once(body, Duration::from_millis(0));
+    assert_eq!(loader.load(&url).unwrap(), json::from_str::<Value>(body).unwrap());
+
+    loader.max_size = body.len() as u64 - 1;
+    let url = serve_once(body, Duration::from_millis(0));
+    assert_eq!(loader.load(&url).unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+    assert_eq!(loader.load("ftp://example.com/").unwrap_err().kind(), io::ErrorKind::InvalidInput);
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn http_loader_timeout() {
+    use std::time::{Duration, Instant};
+
+    let mut loader = http_loader();
+    loader.timeout = Duration::from_millis(100);
+    let url = serve_once("{}", Duration::from_secs(2));
+    let start = Instant::now();
+    assert!(loader.load(&url).is_err());
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
+
+#[cfg(feature = "wasm")]
+#[test]
+fn wasm_rules() {
+    let target = ::wasm::parse_rules("ex = http://example.com/ns#\n\n  \n=http://example.com/a=b\n")
+        .unwrap();
+    assert_eq!(target.rules, vec![
+        ("ex".to_owned(), "http://example.com/ns#".to_owned()),
+        ("".to_owned(), "http://example.com/a=b".to_owned()),
+    ]);
+
+    assert_eq!(::wasm::parse_rules("ex http://example.com/ns#").unwrap_err(),
+        "invalid rule: ex http://example.com/ns#");
+    assert!(::wasm::parse_rules("").unwrap().rules.is_empty());
+}
+
+#[cfg(feature = "presets")]
+#[test]
+fn presets() {
+    let processor = Processor {
+        context: Context::schema_org(),
+        target: TargetContext::common_prefixes(),
+        ..Processor::default()
+    };
+    assert_eq!(processor.process_value(&json!({
+        "@type": "Person",
+        "name": "Alice",
+        "foaf:knows": { "@id": "http://example.com/bob" }
+    })), json!({
+        "@type": ["schema:Person"],
+        "schema:name": "Alice",
+        "foaf:knows": { "@id": "http://example.com/bob" }
+    }));
+}
+
+#[test]
+fn remote_context() {
+    let mut loader = MapLoader::default();
+    loader.0.insert("http://example.com/ctx".to_owned(), json!({
+        "@context": { "@vocab": "http://example.com/vocab#" }
+    }));
+
+    let input = json!({
+        "@context": ["http://example.com/ctx", "http://example.com/missing"],
+        "foo": 1
+    });
+
+    // Without a loader, the reference is ignored.
+    let output = Processor::new().process_value(&input);
+    assert_eq!(output, json!({}));
+
+    let mut processor = Processor::new();
+    processor.loader = Some(Arc::new(loader));
+    let output = processor.process_value(&input);
+    assert_eq!(output, json!({ "http://example.com/vocab#foo": 1 }));
+}
+
+#[test]
+fn cyclic_context() {
+    let mut loader = MapLoader::default();
+    loader.0.insert("http://example.com/a".to_owned(), json!({
+        "@context": ["http://example.com/b", { "a": "http://example.com/a#" }]
+    }));
+    loader.0.insert("http://example.com/b".to_owned(), json!({
+        "@context": ["http://example.com/a", { "b": "http://example.com/b#" }]
+    }));
+
+    let input = json!({
+        "@context": "http://example.com/a",
+        "a:foo": 1,
+        "b:foo": 2
+    });
+
+    let mut processor = Processor::new();
+    processor.loader = Some(Arc::new(loader));
+    let output = processor.process_value(&input);
+    assert_eq!(output, json!({
+        "http://example.com/a#foo": 1,
+        "http://example.com/b#foo": 2
+    }));
+    match processor.process_value_strict(&input) {
+        Err(JsonNsError::CyclicContext(ref iri)) if iri == "http://example.com/a" => {},
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn import_context() {
+    let mut loader = MapLoader::default();
+    loader.0.insert("http://example.com/base".to_owned(), json!({
+        "@context": {
+            "@vocab": "http://example.com/base#",
+            "ex": "http://example.com/base/"
+        }
+    }));
+    loader.0.insert("http://example.com/cycle".to_owned(), json!({
+        "@context": { "@import": "http://example.com/cycle", "ex": "http://example.com/cycle/" }
+    }));
+
+    let input = json!({
+        "@context": {
+            "@import": "http://example.com/base",
+            "ex": "http://example.com/local/"
+        },
+        "foo": 1,
+        "ex:bar": 2
+    });
+
+    let mut processor = Processor::new();
+    processor.loader = Some(Arc::new(loader));
+    let output = processor.process_value(&input);
+    assert_eq!(output, json!({
+        "http://example.com/base#foo": 1,
+        "http://example.com/local/bar": 2
+    }));
+
+    let input = json!({
+        "@context": { "@import": "http://example.com/missing" },
+        "ex:foo": 1
+    });
+    assert_eq!(processor.process_value(&input), json!({ "ex:foo": 1 }));
+    match processor.process_value_strict(&input) {
+        Err(JsonNsError::Loader { ref iri, .. }) if iri == "http://example.com/missing" => {},
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let input = json!({
+        "@context": "http://example.com/cycle",
+        "ex:foo": 1
+    });
+    assert_eq!(processor.process_value(&input), json!({ "http://example.com/cycle/foo": 1 }));
+    match processor.process_value_strict(&input) {
+        Err(JsonNsError::CyclicContext(ref iri)) if iri == "http://example.com/cycle" => {},
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn max_properties() {
+    let input = json!({
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "a": 1,
+        "b": { "c": 2, "d": 3 },
+        "e": 4
+    });
+
+    let mut processor = Processor::new();
+    processor.max_properties = Some(4);
+    assert_eq!(processor.process_value(&input), json!({
+        "http://example.com/vocab#a": 1,
+        "http://example.com/vocab#b": { "http://example.com/vocab#c": 2 }
+    }));
+    match processor.process_value_strict(&input) {
+        Err(JsonNsError::LimitExceeded(Limit::Properties)) => {},
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    processor.max_properties = Some(6);
+    assert!(processor.process_value_strict(&input).is_ok());
+}
+
+#[test]
+fn max_output_nodes() {
+    let input = json!({
+        "@context": {
+            "@vocab": "http://example.com/vocab#",
+            "label": { "@container": ["@language", "@set"] }
+        },
+        "a": 1,
+        "b": { "c": 2, "d": 3 },
+        "label": "Hello",
+        "z": 4
+    });
+
+    // The output has 9 values: the top-level object, 3 for `b`, 3 for the language map with an
+    // array holding the string, and the numbers of `a` and `z`.
+    let mut processor = Processor::new();
+    processor.max_output_nodes = Some(9);
+    let output = processor.process_value_strict(&input).unwrap();
+    assert_eq!(output, processor.process_value(&input));
+
+    processor.max_output_nodes = Some(8);
+    match processor.process_value_strict(&input) {
+        Err(JsonNsError::LimitExceeded(Limit::OutputNodes)) => {},
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // Lenient processing stops adding properties once the limit is reached, here after `b`.
+    processor.max_output_nodes = Some(3);
+    assert_eq!(processor.process_value(&input), json!({
+        "http://example.com/vocab#a": 1,
+        "http://example.com/vocab#b": {
+            "http://example.com/vocab#c": 2,
+            "http://example.com/vocab#d": 3
+        }
+    }));
+
+    // Values are counted as they appear in the output, here after flattening the index map: the
+    // object, the array and 3 strings.
+    let input = json!({
+        "@context": {
+            "@vocab": "http://example.com/vocab#",
+            "tags": { "@container": "@index" }
+        },
+        "tags": { "a": "x", "b": ["y", "z"] }
+    });
+    processor.max_output_nodes = Some(5);
+    assert!(processor.process_value_strict(&input).is_ok());
+    processor.max_output_nodes = Some(4);
+    match processor.process_value_strict(&input) {
+        Err(JsonNsError::LimitExceeded(Limit::OutputNodes)) => {},
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn process_reader() {
+    let input = r#"
+        { "@context": { "ex": "http://example.com/other#" }, "ex:foo": 1 }
+        { "ex:foo": 2 }
+        [{ "ex:foo": 3 }]
+        { "ex:foo":
+    "#;
+
+    let mut processor = Processor::new();
+    processor.context.add_prefix("ex", "http://example.com/ns#");
+    let mut documents = processor.process_reader(input.as_bytes());
+    assert_eq!(documents.next().unwrap().unwrap(), json!({ "http://example.com/other#foo": 1 }));
+    assert_eq!(documents.next().unwrap().unwrap(), json!({ "http://example.com/ns#foo": 2 }));
+    assert_eq!(documents.next().unwrap().unwrap(), json!([{ "http://example.com/ns#foo": 3 }]));
+    match documents.next() {
+        Some(Err(JsonNsError::Json(_))) => {},
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn process_file() {
+    let processor = Processor::new();
+    let output = processor.process_file("tests/contexts/person.json").unwrap();
+    assert_eq!(output, json!({}));
+
+    let error = processor.process_file("tests/contexts/missing.json").unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    let error = processor.process_file("tests/0001.txt").unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    assert!(error.get_ref().unwrap().is::<json::Error>());
+}
+
+#[test]
+fn top_level_scalars() {
+    let mut processor = Processor::new();
+    processor.add_rule("ex", "http://example.com/");
+    for value in &[json!(null), json!(true), json!(42), json!("ex:foo")] {
+        assert_eq!(&processor.process_value(value), value);
+        assert_eq!(&processor.process_value_strict(value).unwrap(), value);
+        match processor.process_document(value) {
+            Err(JsonNsError::NotADocument) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    let input = json!([{ "@context": { "ex": "http://example.com/" }, "ex:foo": 1 }, 2]);
+    assert_eq!(processor.process_document(&input).unwrap(), json!([{ "ex:foo": 1 }, 2]));
+}
+
+#[test]
+fn max_depth() {
+    // Build a value nested deeper than `serde_json` would parse.
+    let mut input = json!({ "@id": "http://example.com/leaf" });
+    for _ in 0..200 {
+        input = json!([{ "http://example.com/next": input }]);
+    }
+
+    let mut processor = Processor::new();
+    processor.max_depth = Some(3);
+    assert_eq!(processor.process_value(&input), json!([{ "http://example.com/next": [{}] }]));
+    match processor.process_value_strict(&input) {
+        Err(JsonNsError::LimitExceeded(Limit::Depth)) => {},
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn max_contexts() {
+    let mut loader = MapLoader::default();
+    loader.0.insert("http://example.com/a".to_owned(), json!({
+        "@context": { "@import": "http://example.com/b", "a": "http://example.com/a/" }
+    }));
+    loader.0.insert("http://example.com/b".to_owned(), json!({
+        "@context": { "b": "http://example.com/b/" }
+    }));
+
+    // Both the reference and the import count towards the limit, also in nested objects.
+    let input = json!({
+        "@context": "http://example.com/a",
+        "a:foo": 1,
+        "a:bar": { "@context": "http://example.com/b", "b:baz": 2 }
+    });
+
+    let mut processor = Processor::new();
+    processor.loader = Some(Arc::new(loader));
+    processor.max_contexts = Some(3);
+    let output = processor.process_value_strict(&input).unwrap();
+    assert_eq!(output, json!({
+        "http://example.com/a/foo": 1,
+        "http://example.com/a/bar": { "http://example.com/b/baz": 2 }
+    }));
+
+    processor.max_contexts = Some(2);
+    match processor.process_value_strict(&input) {
+        Err(JsonNsError::LimitExceeded(Limit::Contexts)) => {},
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // Lenient processing ignores contexts beyond the limit, leaving `b:baz` unresolved.
+    processor.max_contexts = Some(1);
+    assert_eq!(processor.process_value(&input), json!({
+        "http://example.com/a/foo": 1,
+        "http://example.com/a/bar": { "b:baz": 2 }
+    }));
+}
+
+#[test]
+fn memoize_names() {
+    let input = json!({
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "items": [
+            { "@type": "Item", "name": "one" },
+            { "@type": "Item", "name": "two" }
+        ]
+    });
+
+    let mut processor = Processor::new();
+    processor.add_rule("", "http://example.com/vocab#");
+    let expect = processor.process_value(&input);
+    processor.memoize_names = true;
+    assert_eq!(processor.process_value(&input), expect);
+    assert_eq!(expect, json!({
+        "items": [
+            { "@type": ["Item"], "name": "one" },
+            { "@type": ["Item"], "name": "two" }
+        ]
+    }));
+}
+
+#[test]
+fn session() {
+    let documents = vec![
+        json!({ "@context": { "@vocab": "http://example.com/vocab#" }, "name": "Alice" }),
+        json!({ "@context": { "@vocab": "http://example.org/vocab#" }, "name": "Bob" }),
+        json!({ "http://example.com/vocab#name": "Carol" }),
+    ];
+
+    let mut processor = Processor::new();
+    processor.add_rule("", "http://example.com/vocab#");
+    processor.memoize_names = true;
+    processor.max_properties = Some(2);
+    let mut session = processor.session();
+    for document in &documents {
+        assert_eq!(session.process_value(document), processor.process_value(document));
+    }
+    assert_eq!(session.state.names.len(), 2);
+
+    processor.auto_prefix = Some(AutoPrefix::default());
+    let mut session = processor.session();
+    for document in &documents {
+        assert_eq!(session.process_value(document), processor.process_value(document));
+    }
+    assert!(session.state.prefixes.is_empty());
+    assert!(session.state.prefixes.capacity() > 0);
+
+    let document = json!({
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "@type": "Person",
+        "knows": { "@type": "Person", "name": "Bob" }
+    });
+    processor.max_properties = None;
+    processor.order_key = Some("@order".to_owned());
+    let mut session = processor.session();
+    assert_eq!(session.process_value(&document), processor.process_value(&document));
+    assert!(session.state.types.is_empty());
+    assert!(session.state.types.capacity() > 0);
+    assert_eq!(session.state.orders.len(), 2);
+    assert!(session.state.orders.iter().all(|order| order.is_empty() && order.capacity() > 0));
+}
+
+#[test]
+fn flatten() {
+    let input = json!({
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "name": "root",
+        "children": [
+            { "@id": "http://example.com/child", "name": "iri" },
+            { "@id": "_:b0", "name": "labeled" },
+            { "name": "anonymous", "friend": { "@id": "_:b0" } }
+        ]
+    });
+
+    let mut processor = Processor::new();
+    processor.add_rule("", "http://example.com/vocab#");
+    let output = processor.flatten(&input);
+    assert_eq!(output, json!({
+        "@graph": [
+            {
+                "@id": "_:b1",
+                "name": "root",
+                "children": [
+                    { "@id": "http://example.com/child" },
+                    { "@id": "_:b0" },
+                    { "@id": "_:b2" }
+                ]
+            },
+            { "@id": "http://example.com/child", "name": "iri" },
+            { "@id": "_:b0", "name": "labeled" },
+            { "@id": "_:b2", "name": "anonymous", "friend": { "@id": "_:b0" } }
+        ]
+    }));
+    assert_eq!(processor.flatten(&input), output);
+
+    processor.relabel_blank_nodes = true;
+    assert_eq!(processor.flatten(&input), json!({
+        "@graph": [
+            {
+                "@id": "_:b0",
+                "name": "root",
+                "children": [
+                    { "@id": "http://example.com/child" },
+                    { "@id": "_:b1" },
+                    { "@id": "_:b2" }
+                ]
+            },
+            { "@id": "http://example.com/child", "name": "iri" },
+            { "@id": "_:b1", "name": "labeled" },
+            { "@id": "_:b2", "name": "anonymous", "friend": { "@id": "_:b1" } }
+        ]
+    }));
+}
+
+#[test]
+fn flatten_coerced_ids() {
+    let input = json!({
+        "@context": {
+            "@vocab": "http://example.com/v#",
+            "knows": { "@type": "@id" }
+        },
+        "knows": [{ "name": "Bob" }, "http://example.com/carol"]
+    });
+
+    let mut processor = Processor::new();
+    processor.add_rule("", "http://example.com/v#");
+    assert_eq!(processor.flatten(&input), json!({
+        "@graph": [
+            {
+                "@id": "_:b0",
+                "knows": [{ "@id": "_:b1" }, { "@id": "http://example.com/carol" }]
+            },
+            { "@id": "_:b1", "name": "Bob" },
+            { "@id": "http://example.com/carol" }
+        ]
+    }));
+}
+
+#[test]
+fn flatten_merge_nodes() {
+    let input = json!([
+        { "@id": "http://example.com/a", "@type": "Person", "name": "Alice", "age": 30 },
+        { "@id": "http://example.com/b", "knows": { "@id": "http://example.com/a" } },
+        {
+            "@id": "http://example.com/a",
+            "@type": ["Person", "Agent"],
+            "name": "Alice",
+            "age": 31,
+            "tags": ["x", "y"]
+        },
+        { "@id": "http://example.com/a", "tags": ["y", "z"] }
+    ]);
+
+    let mut processor = Processor::new();
+    processor.context.with_vocab("http://example.com/vocab#");
+    processor.add_rule("", "http://example.com/vocab#");
+    assert_eq!(processor.flatten(&input), json!({
+        "@graph": [
+            {
+                "@id": "http://example.com/a",
+                "@type": ["Person", "Agent"],
+                "name": "Alice",
+                "age": [30, 31],
+                "tags": ["x", "y", "z"]
+            },
+            { "@id": "http://example.com/b", "knows": { "@id": "http://example.com/a" } }
+        ]
+    }));
+}
+
+#[test]
+fn auto_prefix() {
+    let input = json!({
+        "@type": "http://schema.org/Person",
+        "http://schema.org/name": "Alice",
+        "http://example.com/vocab#age": 30,
+        "http://example.com/ns/": "no suffix",
+        "urn:isbn:0451450523": "no delimiter",
+        "http://xmlns.com/foaf/0.1/knows": [{ "http://schema.org/name": "Bob" }]
+    });
+
+    let mut processor = Processor::new();
+    processor.add_rule("ns0", "http://example.com/vocab#");
+    processor.auto_prefix = Some(AutoPrefix::default());
+    let expected = json!({
+        "@context": {
+            "ns1": "http://schema.org/",
+            "ns2": "http://xmlns.com/foaf/0.1/"
+        },
+        "@type": ["ns1:Person"],
+        "ns1:name": "Alice",
+        "ns0:age": 30,
+        "http://example.com/ns/": "no suffix",
+        "urn:isbn:0451450523": "no delimiter",
+        "ns2:knows": [{ "ns1:name": "Bob" }]
+    });
+    assert_eq!(processor.process_value(&input), expected);
+
+    processor.memoize_names = true;
+    assert_eq!(processor.process_value(&input), expected);
+
+    let input = json!([{ "http://schema.org/name": "Bob" }]);
+    assert_eq!(processor.process_value(&input), json!({
+        "@context": { "ns1": "http://schema.org/" },
+        "@graph": [{ "ns1:name": "Bob" }]
+    }));
+}
+
+#[test]
+fn id_coercion() {
+    let input = json!({
+        "@context": {
+            "@base": "http://example.com/people/",
+            "schema": "http://schema.org/",
+            "knows": { "@id": "schema:knows", "@type": "@id" },
+            "homepage": { "@id": "http://xmlns.com/foaf/0.1/homepage", "@type": "@id" }
+        },
+        "@id": "alice",
+        "knows": ["bob", { "@id": "carol", "schema:name": "Carol" }],
+        "homepage": "http://alice.example.com/"
+    });
+
+    let mut processor = Processor::new();
+    processor.add_rule("schema", "http://schema.org/");
+    assert_eq!(processor.process_value(&input), json!({
+        "@id": "http://example.com/people/alice",
+        "schema:knows": [
+            "http://example.com/people/bob",
+            { "@id": "http://example.com/people/carol", "schema:name": "Carol" }
+        ],
+        "http://xmlns.com/foaf/0.1/homepage": "http://alice.example.com/"
+    }));
+    assert_eq!(expand(&input, &Context::new())["http://schema.org/knows"][0], json!({
+        "@id": "http://example.com/people/bob"
+    }));
+
+    // With an output context, the coercions are defined, so the output can be expanded again.
+    let mut processor = Processor::new();
+    processor.auto_prefix = Some(AutoPrefix::default());
+    let output = processor.process_value(&input);
+
+    // Prefix labels are generated in the order IRIs are encountered, which depends on key order.
+    let prefix = |iri: &str| output["@context"].as_object().unwrap().iter()
+        .find(|&(_, value)| value == iri)
+        .map(|(key, _)| key.clone())
+        .expect("prefix not defined");
+    let people = prefix("http://example.com/people/");
+    let foaf = prefix("http://xmlns.com/foaf/0.1/");
+    let schema = prefix("http://schema.org/");
+    assert_eq!(output, json!({
+        "@context": {
+            people.clone(): "http://example.com/people/",
+            foaf.clone(): "http://xmlns.com/foaf/0.1/",
+            schema.clone(): "http://schema.org/",
+            format!("{}:homepage", foaf): {
+                "@id": "http://xmlns.com/foaf/0.1/homepage",
+                "@type": "@id"
+            },
+            format!("{}:knows", schema): { "@id": "http://schema.org/knows", "@type": "@id" }
+        },
+        "@id": format!("{}:alice", people),
+        format!("{}:knows", schema): [
+            format!("{}:bob", people),
+            { "@id": format!("{}:carol", people), format!("{}:name", schema): "Carol" }
+        ],
+        format!("{}:homepage", foaf): "http://alice.example.com/"
+    }));
+    assert_eq!(expand(&output, &Context::new()), expand(&input, &Context::new()));
+}
+
+#[test]
+fn type_aliases() {
+    let input = json!({
+        "@context": {
+            "schema": "http://schema.org/",
+            "Person": { "@id": "schema:Person" },
+            "name": { "@id": "schema:name" },
+            "friends": { "@id": "schema:knows", "@container": "@type" }
+        },
+        "@type": ["schema:Person", "http://schema.org/Thing"],
+        "name": "Alice",
+        "friends": {
+            "schema:Person": { "name": "Bob" }
+        }
+    });
+
+    let mut processor = Processor::new();
+    processor.add_rule("schema", "http://schema.org/");
+    assert_eq!(processor.process_value(&input)["@type"], json!(["schema:Person", "schema:Thing"]));
+
+    processor.type_aliases = true;
+    assert_eq!(processor.process_value(&input), json!({
+        "@type": ["Person", "schema:Thing"],
+        "schema:name": "Alice",
+        "schema:knows": [{ "@type": ["Person"], "schema:name": "Bob" }]
+    }));
+}
+
+#[test]
+fn ntriples() {
+    let input = json!({
+        "@context": {
+            "@vocab": "http://example.com/vocab#",
+            "label": { "@container": "@language" }
+        },
+        "@id": "http://example.com/alice",
+        "@type": "Person",
+        "name": "Alice \"A\"\n",
+        "age": 30,
+        "height": 1.7,
+        "active": true,
+        "label": { "en": "Hello" },
+        "born": { "@value": "2000-01-01", "@type": "http://www.w3.org/2001/XMLSchema#date" },
+        "_:hidden": "skipped",
+        "knows": [
+            { "@id": "http://example.com/bob" },
+            { "name": "Anonymous" }
+        ]
+    });
+
+    let mut processor = Processor::new();
+    processor.add_rule("", "http://example.com/vocab#");
+    assert_eq!(processor.to_ntriples(&input), concat!(
+        "<http://example.com/alice> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> ",
+        "<http://example.com/vocab#Person> .\n",
+        "<http://example.com/alice> <http://example.com/vocab#active> ",
+        "\"true\"^^<http://www.w3.org/2001/XMLSchema#boolean> .\n",
+        "<http://example.com/alice> <http://example.com/vocab#age> ",
+        "\"30\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n",
+        "<http://example.com/alice> <http://example.com/vocab#born> ",
+        "\"2000-01-01\"^^<http://www.w3.org/2001/XMLSchema#date> .\n",
+        "<http://example.com/alice> <http://example.com/vocab#height> ",
+        "\"1.7E0\"^^<http://www.w3.org/2001/XMLSchema#double> .\n",
+        "<http://example.com/alice> <http://example.com/vocab#knows> <http://example.com/bob> .\n",
+        "<http://example.com/alice> <http://example.com/vocab#knows> _:b0 .\n",
+        "<http://example.com/alice> <http://example.com/vocab#label> \"Hello\"@en .\n",
+        "<http://example.com/alice> <http://example.com/vocab#name> \"Alice \\\"A\\\"\\n\" .\n",
+        "_:b0 <http://example.com/vocab#name> \"Anonymous\" .\n",
+    ));
+}
+
+#[test]
+fn ntriples_iri_escapes() {
+    let triple = Triple {
+        subject: Subject::Iri("http://example.com/a b".to_owned()),
+        predicate: "http://example.com/vocab#<name>".to_owned(),
+        object: Object::Literal(Literal {
+            value: "Alice".to_owned(),
+            datatype: Some("http://example.com/types#{string}\\".to_owned()),
+            language: None,
+        }),
+    };
+    assert_eq!(triple.to_string(), concat!(
+        "<http://example.com/a\\u0020b> <http://example.com/vocab#\\u003Cname\\u003E> ",
+        "\"Alice\"^^<http://example.com/types#\\u007Bstring\\u007D\\u005C> .",
+    ));
+
+    let input = json!({
+        "@id": "http://example.com/x\"y",
+        "http://example.com/vocab#ref": { "@id": "http://example.com/a|b" }
+    });
+    assert_eq!(Processor::new().to_ntriples(&input), concat!(
+        "<http://example.com/x\\u0022y> <http://example.com/vocab#ref> ",
+        "<http://example.com/a\\u007Cb> .\n",
+    ));
+}
+
+#[test]
+fn triples() {
+    let input = json!({
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "@type": "Person",
+        "name": { "@value": "Alice", "@language": "en" },
+        "knows": { "@id": "http://example.com/bob" }
+    });
+
+    let triples = Processor::new().triples(&input).collect::<Vec<_>>();
+    let subject = Subject::BlankNode("_:b0".to_owned());
+    assert_eq!(triples, vec![
+        Triple {
+            subject: subject.clone(),
+            predicate: "http://www.w3.org/1999/02/22-rdf-syntax-ns#type".to_owned(),
+            object: Object::Iri("http://example.com/vocab#Person".to_owned()),
+        },
+        Triple {
+            subject: subject.clone(),
+            predicate: "http://example.com/vocab#knows".to_owned(),
+            object: Object::Iri("http://example.com/bob".to_owned()),
+        },
+        Triple {
+            subject,
+            predicate: "http://example.com/vocab#name".to_owned(),
+            object: Object::Literal(Literal {
+                value: "Alice".to_owned(),
+                datatype: None,
+                language: Some("en".to_owned()),
+            }),
+        },
+    ]);
+}
+
+#[test]
+fn sort_keys() {
+    let input = json!({
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "@type": "Thing",
+        "@included": [{ "b": 1, "a": 2, "@id": "http://example.com/inc" }],
+        "@id": "http://example.com/self",
+        "1": true,
+        "z": { "y": 1, "@type": "Thing", "x": 2 }
+    });
+
+    let mut processor = Processor::new();
+    processor.add_rule("", "http://example.com/vocab#");
+    processor.sort_keys = true;
+    let output = processor.process_value(&input);
+    assert_eq!(output, Processor { sort_keys: false, ..processor.clone() }.process_value(&input));
+
+    if cfg!(feature = "preserve_order") {
+        assert_eq!(json::to_string(&output).unwrap(), concat!(
+            r#"{"@id":"http://example.com/self","@type":["Thing"],"#,
+            r#""@included":[{"@id":"http://example.com/inc","a":2,"b":1}],"1":true,"#,
+            r#""z":{"@type":["Thing"],"x":2,"y":1}}"#,
+        ));
+    }
+}
+
+#[test]
+fn strip_blank_nodes() {
+    let input = json!({
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "@id": "http://example.com/root",
+        "anonymous": { "name": "Anonymous" },
+        "labeled": { "@id": "_:x", "name": "Labeled" },
+        "value": { "@value": "kept" },
+        "friends": [
+            { "@id": "http://example.com/bob", "knows": { "@id": "_:x" } },
+            { "name": "Anonymous" }
+        ]
+    });
+
+    let mut processor = Processor::new();
+    processor.add_rule("", "http://example.com/vocab#");
+    processor.strip_blank_nodes = true;
+    assert_eq!(processor.process_value(&input), json!({
+        "@id": "http://example.com/root",
+        "value": { "@value": "kept" },
+        "friends": [
+            { "@id": "http://example.com/bob" }
+        ]
+    }));
+    assert_eq!(processor.flatten(&input), json!({
+        "@graph": [
+            {
+                "@id": "http://example.com/root",
+                "value": { "@value": "kept" },
+                "friends": [{ "@id": "http://example.com/bob" }]
+            },
+            { "@id": "http://example.com/bob" }
+        ]
+    }));
+}
+
+#[test]
+fn value_transform() {
+    let input = json!({
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "email": "alice@example.com",
+        "emails": ["bob@example.com", { "email": "carol@example.com", "age": 30 }],
+        "age": 40,
+        "value": { "@value": "dave@example.com" }
+    });
+
+    let processor = Processor::new().with_value_transform(|property, value| {
+        match *value {
+            Value::String(_) if property.ends_with("#email") => json!("[redacted]"),
+            Value::Number(ref number) => json!(number.as_u64().unwrap() + 1),
+            ref value => value.clone(),
+        }
+    });
+    assert_eq!(processor.process_value(&input), json!({
+        "http://example.com/vocab#email": "[redacted]",
+        "http://example.com/vocab#emails": [
+            "bob@example.com",
+            {
+                "http://example.com/vocab#email": "[redacted]",
+                "http://example.com/vocab#age": 31
+            }
+        ],
+        "http://example.com/vocab#age": 41,
+        "http://example.com/vocab#value": { "@value": "dave@example.com" }
+    }));
+
+    let identity = Processor::new().with_value_transform(|_, value| value.clone());
+    assert_eq!(identity.process_value(&input), Processor::new().process_value(&input));
+}
+
+#[test]
+fn property_filters() {
+    let input = json!({
+        "@context": {
+            "@vocab": "http://example.com/vocab#",
+            "ex": "http://example.com/vocab#"
+        },
+        "@id": "http://example.com/alice",
+        "@type": "Person",
+        "name": "Alice",
+        "ex:email": "alice@example.com",
+        "knows": { "name": "Bob", "email": "bob@example.com" }
+    });
+
+    let mut processor = Processor::new();
+    processor.add_rule("", "http://example.com/vocab#");
+    processor.allowed_properties = Some(vec![
+        "http://example.com/vocab#name".to_owned(),
+        "http://example.com/vocab#knows".to_owned(),
+    ].into_iter().collect());
+    assert_eq!(processor.process_value(&input), json!({
+        "@id": "http://example.com/alice",
+        "@type": ["Person"],
+        "name": "Alice",
+        "knows": { "name": "Bob" }
+    }));
+
+    processor.denied_properties.insert("http://example.com/vocab#name".to_owned());
+    assert_eq!(processor.process_value(&input), json!({
+        "@id": "http://example.com/alice",
+        "@type": ["Person"],
+        "knows": {}
+    }));
+
+    processor.allowed_properties = None;
+    processor.denied_properties.insert("http://example.com/vocab#email".to_owned());
+    assert_eq!(processor.process_value(&input), json!({
+        "@id": "http://example.com/alice",
+        "@type": ["Person"],
+        "knows": {}
+    }));
+}
+
+#[test]
+fn rename() {
+    let input = json!({
+        "@context": { "foo": "http://example.com/foo#" },
+        "@id": "http://example.com/self",
+        "foo:id": 1,
+        "foo:name": { "foo:id": 2 },
+        "foo:other": 3
+    });
+
+    let mut processor = Processor::new();
+    processor.add_rule("foo", "http://example.com/foo#");
+    processor.rename.insert("foo:id".to_owned(), "legacyId".to_owned());
+    processor.rename.insert("foo:other".to_owned(), "foo:name".to_owned());
+    assert_eq!(processor.process_value(&input), json!({
+        "@id": "http://example.com/self",
+        "legacyId": 1,
+        "foo:name": 3
+    }));
+}
+
+#[test]
+fn lowercase() {
+    let input = json!({
+        "@context": {
+            "@vocab": "http://example.com/vocab#",
+            "Ex": "http://example.com/ex#"
+        },
+        "Name": "a",
+        "name": "z",
+        "Ex:Title": "b",
+        "http://Example.com/Other": "c"
+    });
+
+    // Names that become equal collide like renamed properties, so `name` replaces `Name`.
+    let mut processor = Processor::new();
+    processor.add_rule("", "http://example.com/vocab#");
+    processor.add_rule("Ex", "http://example.com/ex#");
+    processor.lowercase = Some(Lowercase::Local);
+    assert_eq!(processor.process_value(&input), json!({
+        "name": "z",
+        "Ex:title": "b",
+        "http://Example.com/Other": "c"
+    }));
+
+    processor.lowercase = Some(Lowercase::All);
+    processor.rename.insert("ex:title".to_owned(), "title".to_owned());
+    assert_eq!(processor.process_value(&input), json!({
+        "name": "z",
+        "title": "b",
+        "http://Example.com/Other": "c"
+    }));
+}
+
+#[test]
+fn protected_terms() {
+    let input = json!({
+        "@context": {
+            "@protected": true,
+            "ex": "http://example.com/ex#"
+        },
+        "ex:foo": {
+            "@context": { "ex": "http://example.com/ex#" },
+            "ex:bar": 1
+        }
+    });
+    assert!(Processor::new().process_value_strict(&input).is_ok());
+
+    let input = json!({
+        "@context": {
+            "@protected": true,
+            "ex": "http://example.com/ex#"
+        },
+        "ex:foo": {
+            "@context": { "ex": "http://example.com/other#" },
+            "ex:bar": 1
+        }
+    });
+    match Processor::new().process_value_strict(&input) {
+        Err(JsonNsError::ProtectedTerm(ref term)) if term == "ex" => {},
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn unknown_prefix() {
+    let input = json!({
+        "@context": [
+            { "ex": "http://example.com/ex#" },
+            null
+        ],
+        "ex:foo": 1,
+        "exx:foo": 2,
+        "HTTP://example.com/bar": 3,
+        "urn:example:baz": 4,
+        "custom:qux": 5
+    });
+
+    let mut processor = Processor::new();
+    assert_eq!(processor.process_value(&input).as_object().unwrap().len(), 5);
+
+    processor.context.unknown_prefix = UnknownPrefix::Drop;
+    processor.context.schemes.insert("custom".to_owned());
+    assert_eq!(processor.process_value(&input), json!({
+        "HTTP://example.com/bar": 3,
+        "urn:example:baz": 4,
+        "custom:qux": 5
+    }));
+}
+
+#[test]
+fn unknown_prefix_vocab() {
+    let input = json!({
+        "@context": {
+            "@vocab": "http://example.com/vocab#",
+            "ex": "http://example.com/ex#"
+        },
+        "ex:foo": 1,
+        "exx:foo": 2,
+        "urn:example:baz": 3
+    });
+
+    let mut processor = Processor::new();
+    processor.context.unknown_prefix = UnknownPrefix::Vocab;
+    assert_eq!(processor.process_value(&input), json!({
+        "http://example.com/ex#foo": 1,
+        "http://example.com/vocab#exx:foo": 2,
+        "urn:example:baz": 3
+    }));
+
+    let context = Context { unknown_prefix: UnknownPrefix::Vocab, ..Context::new() };
+    assert_eq!(context.expand_name("exx:foo"), None);
+
+    let input = json!([
+        { "@context": { "@vocab": "http://example.com/vocab#" }, "@id": "foo:bar", "name": 1 },
+        { "@context": { "@vocab": "http://example.com/vocab#" }, "@id": "urn:example:a", "name": 2 }
+    ]);
+    assert_eq!(processor.process_value(&input), json!([
+        { "http://example.com/vocab#name": 1 },
+        { "@id": "urn:example:a", "http://example.com/vocab#name": 2 }
+    ]));
+}
+
+#[test]
+fn language_arrays() {
+    let input = json!({
+        "@context": {
+            "@vocab": "http://example.com/vocab#",
+            "tags": { "@container": "@language" }
+        },
+        "tags": {
+            "en": ["one", 2, "three"],
+            "de": [4],
+            "fr": "un"
+        }
+    });
+
+    let mut processor = Processor::new();
+    assert_eq!(processor.process_value(&input), json!({
+        "http://example.com/vocab#tags": { "fr": "un" }
+    }));
+
+    processor.language_arrays = true;
+    assert_eq!(processor.process_value(&input), json!({
+        "http://example.com/vocab#tags": { "en": ["one", "three"], "fr": "un" }
+    }));
+}
+
+#[test]
+fn wrap_strings() {
+    let input = json!({
+        "@context": {
+            "@vocab": "http://example.com/vocab#",
+            "label": { "@container": "@language" }
+        },
+        "name": "Alice",
+        "age": 30,
+        "tags": ["a", "b"],
+        "label": { "en": "Hello" },
+        "child": {
+            "@context": { "@language": "de" },
+            "name": "Bob"
+        }
+    });
+
+    let mut processor = Processor::new();
+    processor.wrap_strings = true;
+    assert_eq!(processor.process_value(&input), json!({
+        "http://example.com/vocab#name": { "@none": "Alice" },
+        "http://example.com/vocab#age": 30,
+        "http://example.com/vocab#tags": ["a", "b"],
+        "http://example.com/vocab#label": { "en": "Hello" },
+        "http://example.com/vocab#child": {
+            "http://example.com/vocab#name": { "de": "Bob" }
+        }
+    }));
+}
+
+#[test]
+fn index_key() {
+    let input = json!({
+        "@context": {
+            "@vocab": "http://example.com/vocab#",
+            "posts": { "@container": "@index" }
+        },
+        "posts": {
+            "first": { "title": "Hello" },
+            "second": ["plain", { "@value": "tagged", "@language": "en" }],
+            "@none": "unindexed"
+        }
+    });
+
+    let mut processor = Processor::new();
+    processor.index_key = Some("@index".to_owned());
+    let mut output = processor.process_value(&input);
+    // Entries follow the key order of the index map, which depends on the `preserve_order`
+    // feature. The sort is stable, so values under the same key stay in order.
+    output["http://example.com/vocab#posts"].as_array_mut().unwrap()
+        .sort_by_key(|value| value["@index"].as_str().unwrap_or("").to_owned());
+    assert_eq!(output, json!({
+        "http://example.com/vocab#posts": [
+            "unindexed",
+            { "http://example.com/vocab#title": "Hello", "@index": "first" },
+            { "@value": "plain", "@index": "second" },
+            { "@value": "tagged", "@language": "en", "@index": "second" }
+        ]
+    }));
+}
+
+#[test]
+fn trim_strings() {
+    let input = json!({
+        "@context": {
+            "@vocab": "http://example.com/vocab#",
+            "label": { "@container": "@language" },
+            "tags": { "@container": ["@language", "@set"] }
+        },
+        "name": "  Alice\n",
+        "list": [" a", { "@value": "b ", "@language": "en" }],
+        "label": { "en": " Hello " },
+        "tags": "\tx"
+    });
+
+    let mut processor = Processor::new();
+    processor.trim_strings = true;
+    assert_eq!(processor.process_value(&input), json!({
+        "http://example.com/vocab#name": "Alice",
+        "http://example.com/vocab#list": ["a", { "@value": "b", "@language": "en" }],
+        "http://example.com/vocab#label": { "en": "Hello" },
+        "http://example.com/vocab#tags": { "": ["x"] }
+    }));
+}
+
+#[test]
+fn untagged_key() {
+    let input = json!({
+        "@context": {
+            "@vocab": "http://example.com/vocab#",
+            "label": { "@container": "@language" }
+        },
+        "name": "Alice",
+        "label": "Hello",
+        "child": {
+            "@context": { "@language": "de" },
+            "label": "Hallo"
+        }
+    });
+
+    let mut processor = Processor::new();
+    processor.untagged_key = Some("und".to_owned());
+    assert_eq!(processor.process_value(&input), json!({
+        "http://example.com/vocab#name": "Alice",
+        "http://example.com/vocab#label": { "und": "Hello" },
+        "http://example.com/vocab#child": {
+            "http://example.com/vocab#label": { "de": "Hallo" }
+        }
+    }));
+
+    processor.wrap_strings = true;
+    assert_eq!(processor.process_value(&input)["http://example.com/vocab#name"], json!({
+        "und": "Alice"
+    }));
+}
+
+#[test]
+fn number_precision() {
+    let input: Value = json::from_str(r#"{
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "big": 123456789012345678901234567890,
+        "precise": 0.10000000000000000555111512312578270211815834045,
+        "value": { "@value": 18446744073709551617 }
+    }"#).unwrap();
+
+    let output = Processor::new().process_value(&input);
+    assert_eq!(output["http://example.com/vocab#big"], input["big"]);
+    assert_eq!(output["http://example.com/vocab#precise"], input["precise"]);
+    assert_eq!(output["http://example.com/vocab#value"], input["value"]);
+
+    let expanded = expand(&input, &Context::new());
+    assert_eq!(expanded["http://example.com/vocab#big"][0]["@value"], input["big"]);
+    assert_eq!(expanded["http://example.com/vocab#value"][0], input["value"]);
+
+    if cfg!(feature = "arbitrary_precision") {
+        let output = json::to_string(&output).unwrap();
+        assert!(output.contains("123456789012345678901234567890"));
+        assert!(output.contains("0.10000000000000000555111512312578270211815834045"));
+        assert!(output.contains("18446744073709551617"));
+    }
+}
+
+#[test]
+fn number_types() {
+    // Note that `Value` equality distinguishes integers from floats.
+    assert_ne!(json!(42), json!(42.0));
+
+    let input = json!({
+        "@context": {
+            "@vocab": "http://example.com/vocab#",
+            "raw": { "@type": "@json" }
+        },
+        "int": 42,
+        "float": 42.0,
+        "value": { "@value": 42.0 },
+        "raw": [42, 42.0]
+    });
+
+    assert_eq!(Processor::new().process_value(&input), json!({
+        "http://example.com/vocab#int": 42,
+        "http://example.com/vocab#float": 42.0,
+        "http://example.com/vocab#value": { "@value": 42.0 },
+        "http://example.com/vocab#raw": [42, 42.0]
+    }));
+    assert_eq!(expand(&input, &Context::new()), json!({
+        "http://example.com/vocab#int": [{ "@value": 42 }],
+        "http://example.com/vocab#float": [{ "@value": 42.0 }],
+        "http://example.com/vocab#value": [{ "@value": 42.0 }],
+        "http://example.com/vocab#raw": [{ "@value": [42, 42.0], "@type": "@json" }]
+    }));
+}
+
+#[test]
+fn expand_document() {
+    let mut context = Context::new();
+    context.with_vocab("http://example.com/vocab#");
+    let input = json!({
+        "@context": {
+            "@language": "en",
+            "label": { "@container": "@language" },
+            "raw": { "@type": "@json" }
+        },
+        "@type": "Thing",
+        "name": "Alice",
+        "raw": { "a": [1] },
+        "label": "Hello",
+        "tags": ["a", "b"],
+        "count": [1, true, null],
+        "child": { "name": "Bob" }
+    });
+
+    assert_eq!(expand(&input, &context), json!({
+        "@type": ["http://example.com/vocab#Thing"],
+        "http://example.com/vocab#name": [{ "@value": "Alice", "@language": "en" }],
+        "http://example.com/vocab#raw": [{ "@value": { "a": [1] }, "@type": "@json" }],
+        "http://example.com/vocab#label": [{ "@value": "Hello", "@language": "en" }],
+        "http://example.com/vocab#tags": [
+            { "@value": "a", "@language": "en" },
+            { "@value": "b", "@language": "en" }
+        ],
+        "http://example.com/vocab#count": [{ "@value": 1 }, { "@value": true }, null],
+        "http://example.com/vocab#child": [{
+            "http://example.com/vocab#name": [{ "@value": "Bob", "@language": "en" }]
+        }]
+    }));
+}
+
+#[test]
+fn version() {
+    let context = Context::from(&json!({ "@version": 1.1 }));
+    assert_eq!(context.version(), Some("1.1"));
+
+    let context = Context::from(&json!({ "@version": "1.1" }));
+    assert_eq!(context.version(), None);
+    assert_eq!(Context::validate(&json!({ "@version": "1.1" })), vec![
+        Warning::InvalidDefinition("/@version".to_owned()),
+    ]);
+    assert_eq!(Context::validate(&json!({ "@version": 1.1 })), vec![]);
+
+    // An invalid version is ignored, also in strict mode.
+    let input = json!({
+        "@context": { "@version": 1.0, "@vocab": "http://example.com/vocab#" },
+        "name": "Alice"
+    });
+    let mut processor = Processor::new();
+    let output = json!({ "http://example.com/vocab#name": "Alice" });
+    assert_eq!(processor.process_value(&input), output);
+    assert_eq!(processor.process_value_strict(&input).unwrap(), output);
+    assert_eq!(Context::validate(&input["@context"]), vec![
+        Warning::InvalidDefinition("/@version".to_owned()),
+    ]);
+
+    // Features of JSON-LD 1.1 can be refused if the version is not declared.
+    let input = json!({
+        "@context": {
+            "@vocab": "http://example.com/vocab#",
+            "child": { "@context": { "@language": "en" } }
+        },
+        "@nest": { "name": "Alice" },
+        "child": { "name": "Bob" }
+    });
+    let output = processor.process_value_strict(&input).unwrap();
+    processor.require_version = true;
+    assert_eq!(processor.process_value(&input), output);
+    match processor.process_value_strict(&input) {
+        Err(JsonNsError::VersionRequired(ref feature)) if feature == "@context" => {},
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let mut versioned = input.clone();
+    versioned["@context"]["@version"] = json!(1.1);
+    assert_eq!(processor.process_value_strict(&versioned).unwrap(), output);
+
+    let input = json!({
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "@nest": { "name": "Alice" }
+    });
+    match processor.process_value_strict(&input) {
+        Err(JsonNsError::VersionRequired(ref feature)) if feature == "@nest" => {},
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn direction() {
+    let mut context = Context::new();
+    context.with_vocab("http://example.com/vocab#");
+    let input = json!({
+        "@context": {
+            "@language": "ar",
+            "@direction": "rtl",
+            "label": { "@container": "@language" }
+        },
+        "label": "مرحبا",
+        "title": { "@value": "Hello", "@language": "en", "@direction": "ltr" },
+        "plain": {
+            "@context": { "@direction": null },
+            "label": "مرحبا"
+        }
+    });
+
+    assert_eq!(expand(&input, &context), json!({
+        "http://example.com/vocab#label": [
+            { "@value": "مرحبا", "@language": "ar", "@direction": "rtl" }
+        ],
+        "http://example.com/vocab#title": [
+            { "@value": "Hello", "@language": "en", "@direction": "ltr" }
+        ],
+        "http://example.com/vocab#plain": [{
+            "http://example.com/vocab#label": [{ "@value": "مرحبا", "@language": "ar" }]
+        }]
+    }));
+
+    let mut processor = Processor::new();
+    processor.context = context;
+    assert_eq!(processor.process_value(&input)["http://example.com/vocab#label"], json!({
+        "ar": "مرحبا"
+    }));
+}
+
+#[test]
+fn term_direction() {
+    let mut context = Context::new();
+    context.with_vocab("http://example.com/vocab#");
+    let input = json!({
+        "@context": {
+            "@language": "ar",
+            "@direction": "rtl",
+            "label": { "@container": "@language", "@direction": "ltr" },
+            "name": { "@direction": null },
+            "bad": { "@direction": "up" }
+        },
+        "label": { "en": "Hello" },
+        "name": "مرحبا",
+        "bad": "مرحبا"
+    });
+
+    assert_eq!(expand(&input, &context), json!({
+        "http://example.com/vocab#label": [
+            { "@value": "Hello", "@language": "en", "@direction": "ltr" }
+        ],
+        "http://example.com/vocab#name": [{ "@value": "مرحبا", "@language": "ar" }],
+        "http://example.com/vocab#bad": [
+            { "@value": "مرحبا", "@language": "ar", "@direction": "rtl" }
+        ]
+    }));
+    assert_eq!(Context::validate(&input["@context"]), vec![
+        Warning::InvalidDefinition("/bad/@direction".to_owned()),
+    ]);
+}
+
+#[test]
+fn compact_document() {
+    let mut target = TargetContext::new();
+    target.add_rule("", "http://example.com/vocab#");
+    target.add_rule("xsd", "http://www.w3.org/2001/XMLSchema#");
+    let input = json!({
+        "@id": "http://example.com/vocab#self",
+        "@type": ["http://example.com/vocab#Thing"],
+        "http://example.com/vocab#count": [{
+            "@value": "5",
+            "@type": "http://www.w3.org/2001/XMLSchema#integer"
+        }],
+        "http://example.com/vocab#child": [{
+            "http://example.com/other#name": ["Bob"]
+        }]
+    });
+
+    assert_eq!(compact(&input, &target), json!({
+        "@id": "self",
+        "@type": ["Thing"],
+        "count": [{ "@value": "5", "@type": "xsd:integer" }],
+        "child": [{ "http://example.com/other#name": ["Bob"] }]
+    }));
+
+    // Compacting the expanded form matches what the processor does, apart from arrays and value
+    // objects.
+    let document = json!({
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "name": "Alice"
+    });
+    assert_eq!(compact(&expand(&document, &Context::new()), &target), json!({
+        "name": [{ "@value": "Alice" }]
+    }));
+}
+
+#[test]
+fn process_documents() {
+    let documents = vec![
+        json!({
+            "@context": { "@vocab": "http://example.com/one#" },
+            "foo": 1
+        }),
+        json!({
+            "foo": 2,
+            "ext:foo": 3
+        }),
+    ];
+
+    let mut processor = Processor::new();
+    processor.context.add_prefix("ext", "http://example.com/ext#");
+    assert_eq!(processor.process_documents(&documents), vec![
+        json!({ "http://example.com/one#foo": 1 }),
+        json!({ "http://example.com/ext#foo": 3 }),
+    ]);
+}
+
+#[test]
+fn strict_errors() {
+    let input = json!({
+        "@context": { "ex": "http://example.com/ex#" },
+        "ex:foo": 1,
+        "bar": 2,
+        "ex:nested": [{}, { "baz/qux": 3 }]
+    });
+    match Processor::new().process_value_strict(&input) {
+        Err(JsonNsError::Dropped(ref dropped)) => {
+            assert_eq!(dropped, &vec![
+                Dropped { property: "bar".to_owned(), path: "/bar".to_owned() },
+                Dropped {
+                    property: "baz/qux".to_owned(),
+                    path: "/ex:nested/1/baz~1qux".to_owned(),
+                },
+            ]);
+        },
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let object = input.as_object().unwrap();
+    match Processor::new().process_object_strict(object) {
+        Err(JsonNsError::Dropped(ref dropped)) => assert_eq!(dropped.len(), 2),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    let input = json!({ "ex:foo": 1 });
+    let object = input.as_object().unwrap();
+    assert_eq!(Processor::new().process_object_strict(object).unwrap(), *object);
+
+    let input = json!({
+        "@context": "http://example.com/missing",
+        "ex:foo": 1
+    });
+    let mut processor = Processor::new();
+    processor.loader = Some(Arc::new(MapLoader::default()));
+    assert_eq!(processor.process_value(&input), json!({ "ex:foo": 1 }));
+    match processor.process_value_strict(&input) {
+        Err(JsonNsError::Loader { ref iri, .. }) if iri == "http://example.com/missing" => {},
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn validate_context() {
+    let context = json!([
+        "http://example.com/remote",
+        {
+            "@vocab": "vocab",
+            "@base": 5,
+            "ex": "http://example.com/ex#",
+            "bad": "not-an-iri",
+            "a:b": "http://example.com/ab#",
+            "ctx": { "@id": "@context" },
+            "type": { "@id": "@type" },
+            "list": { "@container": "@list" },
+            "set": { "@container": ["@set", "@language"] },
+            "sets": { "@container": ["@set", "@language", "@type"] },
+            "scoped": {
+                "@id": "ex:scoped",
+                "@context": { "x/y": 3 },
+                "@unknown": true
+            },
+            "number": 5
+        }
+    ]);
+
+    // Warnings follow the key order of the context, which depends on the `preserve_order`
+    // feature, so compare them sorted by path.
+    let mut warnings = Context::validate(&context);
+    warnings.sort_by_key(Warning::to_string);
+    assert_eq!(warnings, vec![
+        Warning::InvalidDefinition("/1/@base".to_owned()),
+        Warning::InvalidIri("/1/@vocab".to_owned()),
+        Warning::InvalidDefinition("/1/a:b".to_owned()),
+        Warning::InvalidIri("/1/bad".to_owned()),
+        Warning::KeywordAlias("/1/ctx/@id".to_owned()),
+        Warning::UnknownContainer("/1/list/@container".to_owned()),
+        Warning::InvalidDefinition("/1/number".to_owned()),
+        Warning::InvalidDefinition("/1/scoped/@context/x~1y".to_owned()),
+        Warning::UnknownKeyword("/1/scoped/@unknown".to_owned()),
+        Warning::UnknownContainer("/1/sets/@container".to_owned()),
+    ]);
+    assert_eq!(Context::validate(&json!({ "ex": "http://example.com/ex#" })), vec![]);
+}
+
+#[test]
+fn resolve_name() {
+    let mut context = Context::new();
+    context.with_vocab("http://example.com/vocab#")
+        .add_alias("kind", "@type")
+        .add_alias("name", "foaf:name");
+    assert_eq!(context.resolve_name("kind"), Name::Keyword("@type"));
+    assert_eq!(context.resolve_name("@id"), Name::Keyword("@id"));
+    assert_eq!(context.resolve_name("title"), Name::Iri("http://example.com/vocab#title".into()));
+    assert_eq!(context.resolve_name("name"), Name::Iri("foaf:name".into()));
+
+    context.ns = None;
+    assert_eq!(context.resolve_name("title"), Name::Drop);
+}
+
+#[test]
+fn process_value_visit() {
+    let mut context = Context::new();
+    context.with_vocab("http://example.com/vocab#");
+    let input = json!({
+        "@id": "http://example.com/doc",
+        "name": "Alice",
+        "friend": { "name": "Bob" }
+    });
+
+    let mut processor = Processor::new();
+    processor.context = context;
+    let mut visited = Vec::new();
+    let output = processor.process_value_visit(&input, &mut |name: &str, value: &Value| {
+        visited.push((name.to_owned(), value.clone()));
+    });
+    assert_eq!(output, processor.process_value(&input));
+
+    // Nested properties are visited before the property containing them.
+    let position = |value: Value| visited.iter().position(|entry| entry.1 == value).unwrap();
+    assert_eq!(visited.len(), 3);
+    assert!(position(json!("Bob")) < position(json!({ "http://example.com/vocab#name": "Bob" })));
+    assert_eq!(visited[position(json!("Alice"))].0, "http://example.com/vocab#name");
+}
+
+#[test]
+fn roundtrip_check() {
+    let input = json!({
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "@type": "http://example.com/ns#Thing",
+        "foo": 1,
+        "http://example.com/ns#bar": { "baz": [true] },
+        "http://example.org/other": "unmatched"
+    });
+
+    let mut processor = Processor::new();
+    processor
+        .add_rule("", "http://example.com/vocab#")
+        .add_rule("ex", "http://example.com/ns#");
+    assert!(processor.roundtrip_check(&input));
+
+    processor.auto_prefix = Some(AutoPrefix::default());
+    assert!(processor.roundtrip_check(&input));
+
+    // The remainder of this name contains a colon, so it reads as a CURIE.
+    let input = json!({ "http://example.com/vocab#foo:bar": 1 });
+    assert!(!processor.roundtrip_check(&input));
+
+    processor.rename.insert("ex:bar".to_owned(), "bar".to_owned());
+    let input = json!({ "http://example.com/ns#bar": 1 });
+    assert!(!processor.roundtrip_check(&input));
+}
+
+#[test]
+fn order_key() {
+    let input = json!({
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "b": 1,
+        "@type": "Thing",
+        "a": { "d": 2, "c": 3 }
+    });
+
+    let mut processor = Processor::new();
+    processor.add_rule("", "http://example.com/vocab#");
+    processor.order_key = Some("my:order".to_owned());
+    let output = processor.process_value(&input);
+
+    // Without `preserve_order`, input keys are sorted too.
+    let expected = if cfg!(feature = "preserve_order") {
+        (json!(["b", "@type", "a"]), json!(["d", "c"]))
+    } else {
+        (json!(["@type", "a", "b"]), json!(["c", "d"]))
+    };
+    assert_eq!(output["my:order"], expected.0);
+    assert_eq!(output["a"]["my:order"], expected.1);
+}
+
+#[test]
+fn pairs() {
+    let input = json!({
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "name": "Alice",
+        "friend": { "name": "Bob" }
+    });
+
+    let mut processor = Processor::new();
+    processor.add_rule("", "http://example.com/vocab#");
+    let mut pairs = processor.pairs(&input).collect::<Vec<_>>();
+    pairs.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(pairs, vec![
+        ("friend".to_owned(), json!({ "name": "Bob" })),
+        ("name".to_owned(), json!("Alice")),
+    ]);
+    assert_eq!(processor.pairs(&json!([input])).count(), 0);
+}
+
+#[test]
+fn absolute_iri() {
+    for iri in &["http://example.com/", "HTTP://example.com/", "Http://example.com/"] {
+        assert!(is_absolute_iri(iri), "{}", iri);
+    }
+    for iri in &["urn:isbn:0451450523", "URN:ISBN:0451450523", "svn+ssh://host/", "_:b0"] {
+        assert!(is_absolute_iri(iri), "{}", iri);
+    }
+    for iri in &["example.com", ":foo", "1http://example.com/", "@id:foo", "ht tp://example.com/"] {
+        assert!(!is_absolute_iri(iri), "{}", iri);
+    }
+
+    let input = json!({ "@id": "HTTP://example.com/Doc", "HTTP://example.com/Prop": 1 });
+    assert_eq!(Processor::new().process_value(&input), input);
+}
+
+#[test]
+fn resolve_iri() {
+    // Examples from RFC 3986, section 5.4.
+    let base = "http://a/b/c/d;p?q";
+    let examples = [
+        ("g:h", "g:h"), ("g", "http://a/b/c/g"), ("./g", "http://a/b/c/g"),
+        ("g/", "http://a/b/c/g/"), ("/g", "http://a/g"), ("//g", "http://g"),
+        ("?y", "http://a/b/c/d;p?y"), ("g?y", "http://a/b/c/g?y"), ("#s", "http://a/b/c/d;p?q#s"),
+        ("g#s", "http://a/b/c/g#s"), (";x", "http://a/b/c/;x"), ("", "http://a/b/c/d;p?q"),
+        (".", "http://a/b/c/"), ("./", "http://a/b/c/"), ("..", "http://a/b/"),
+        ("../", "http://a/b/"), ("../g", "http://a/b/g"), ("../..", "http://a/"),
+        ("../../g", "http://a/g"), ("../../../g", "http://a/g"), ("/./g", "http://a/g"),
+        ("/../g", "http://a/g"), ("g.", "http://a/b/c/g."), ("..g", "http://a/b/c/..g"),
+        ("./../g", "http://a/b/g"), ("g/./h", "http://a/b/c/g/h"), ("g/../h", "http://a/b/c/h"),
+        ("g;x=1/../y", "http://a/b/c/y"), ("g?y/./x", "http://a/b/c/g?y/./x"),
+    ];
+    for &(reference, expected) in &examples {
+        assert_eq!(iri::resolve(base, reference), expected, "{}", reference);
+    }
+}
+
+#[test]
+fn process_value_with_base() {
+    let input = json!({
+        "@context": { "@vocab": "http://example.com/vocab#" },
+        "@id": "doc",
+        "parts": [{ "@id": "../other#part" }, { "@id": "_:b0" }, { "@id": "urn:x:part" }]
+    });
+
+    let processor = Processor::new();
+    assert_eq!(processor.process_value(&input), json!({
+        "http://example.com/vocab#parts": [{}, { "@id": "_:b0" }, { "@id": "urn:x:part" }]
+    }));
+    assert_eq!(processor.process_value_with_base(&input, "http://example.com/docs/"), json!({
+        "@id": "http://example.com/docs/doc",
+        "http://example.com/vocab#parts": [
+            { "@id": "http://example.com/other#part" },
+            { "@id": "_:b0" },
+            { "@id": "urn:x:part" }
+        ]
+    }));
+
+    // A base IRI given to the processor overrides `@base` in the context.
+    let input = json!({
+        "@context": [{ "@base": "http://example.com/a/" }, { "@base": "b/" }],
+        "@id": "doc"
+    });
+    assert_eq!(processor.process_value(&input), json!({ "@id": "http://example.com/a/b/doc" }));
+    assert_eq!(processor.process_value_with_base(&input, "http://example.org/"), json!({
+        "@id": "http://example.org/doc"
+    }));
+}
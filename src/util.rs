@@ -0,0 +1,44 @@
+//! Utilities for working with JSON-LD style values.
+
+use json::Value;
+use std::slice::Iter;
+
+/// Iterator used to walk a value that may or may not be an array.
+///
+/// Many keywords accept either a single value or an array of values, such as `@type` and
+/// `@container`. Created from a `&Value`, this yields the elements of an array, or otherwise the
+/// value itself, including `null`. Values are borrowed, not copied.
+#[derive(Clone,Debug)]
+pub enum OneOrMany<'a> {
+    /// Nothing is left to yield.
+    None,
+    /// A single value is left to yield.
+    One(&'a Value),
+    /// The remaining elements of an array.
+    Many(Iter<'a, Value>),
+}
+
+impl<'a> From<&'a Value> for OneOrMany<'a> {
+    fn from(value: &'a Value) -> Self {
+        match *value {
+            Value::Array(ref arr) => OneOrMany::Many(arr.iter()),
+            ref value => OneOrMany::One(value),
+        }
+    }
+}
+
+impl<'a> Iterator for OneOrMany<'a> {
+    type Item = &'a Value;
+    fn next(&mut self) -> Option<&'a Value> {
+        match *self {
+            OneOrMany::None => None,
+            OneOrMany::One(value) => {
+                *self = OneOrMany::None;
+                Some(value)
+            },
+            OneOrMany::Many(ref mut iter) => {
+                iter.next()
+            },
+        }
+    }
+}
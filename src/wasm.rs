@@ -0,0 +1,41 @@
+//! Bindings for use from JavaScript, enabled with the `wasm` feature.
+//!
+//! The `process` function is exported using `wasm-bindgen`, and works on JSON strings so no
+//! conversion of values is necessary on the JavaScript side.
+
+use json::{self, Value};
+use wasm_bindgen::prelude::*;
+use {Processor, TargetContext};
+
+/// Process a JSON-NS document.
+///
+/// The input is a JSON string. The rules are a newline separated list of `prefix=base` pairs,
+/// which are added to the target context in order. Blank lines are ignored.
+///
+/// Returns the processed document as a JSON string, or throws an exception if the input or rules
+/// could not be parsed.
+#[wasm_bindgen]
+pub fn process(input: &str, rules: &str) -> Result<String, JsValue> {
+    let target = parse_rules(rules).map_err(|err| JsValue::from_str(&err))?;
+    let processor = Processor::new().with_target(target);
+
+    let input: Value = json::from_str(input)
+        .map_err(|err| JsValue::from_str(&format!("invalid input: {}", err)))?;
+    let output = processor.process_value(&input);
+    json::to_string(&output)
+        .map_err(|err| JsValue::from_str(&err.to_string()))
+}
+
+/// Parse a newline separated list of `prefix=base` rules, ignoring blank lines.
+pub(crate) fn parse_rules(rules: &str) -> Result<TargetContext, String> {
+    let mut target = TargetContext::new();
+    for line in rules.lines().filter(|line| !line.trim().is_empty()) {
+        let mut parts = line.splitn(2, '=');
+        let prefix = parts.next().unwrap().trim();
+        let base = parts.next()
+            .ok_or_else(|| format!("invalid rule: {}", line))?
+            .trim();
+        target.add_rule(prefix, base);
+    }
+    Ok(target)
+}